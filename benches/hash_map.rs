@@ -0,0 +1,61 @@
+use std::{collections::HashMap, hint::black_box, time::Instant};
+
+use algo::{
+    collections::fast_hash::{FastHashMap, U64HashMap},
+    random::XorShift,
+};
+
+fn bench(name: &str, f: impl FnOnce()) {
+    let start = Instant::now();
+    f();
+    println!("{name:<40} {:>10.2?}", start.elapsed());
+}
+
+fn main() {
+    let n = 1_000_000;
+    let mut rand = XorShift::new(0);
+    let random_keys = (0..n).map(|_| rand.next()).collect::<Vec<_>>();
+    let sequential_keys = (0..n as u64).collect::<Vec<_>>();
+
+    for (keys_name, keys) in [("random", &random_keys), ("sequential", &sequential_keys)] {
+        bench(&format!("std HashMap {keys_name}"), || {
+            let mut map = HashMap::new();
+            for &key in keys {
+                map.insert(key, key);
+            }
+            for &key in keys {
+                black_box(map.get(&key));
+            }
+            for &key in keys {
+                map.remove(&key);
+            }
+            black_box(map.len());
+        });
+        bench(&format!("FastHashMap {keys_name}"), || {
+            let mut map = FastHashMap::default();
+            for &key in keys {
+                map.insert(key, key);
+            }
+            for &key in keys {
+                black_box(map.get(&key));
+            }
+            for &key in keys {
+                map.remove(&key);
+            }
+            black_box(map.len());
+        });
+        bench(&format!("U64HashMap {keys_name}"), || {
+            let mut map = U64HashMap::new();
+            for &key in keys {
+                map.insert(key, key);
+            }
+            for &key in keys {
+                black_box(map.get(key));
+            }
+            for &key in keys {
+                map.remove(key);
+            }
+            black_box(map.len());
+        });
+    }
+}
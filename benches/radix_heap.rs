@@ -0,0 +1,57 @@
+use std::{cmp::Reverse, collections::BinaryHeap, hint::black_box, time::Instant};
+
+use algo::{collections::radix_heap::RadixHeap, random::XorShift};
+
+fn bench(name: &str, f: impl FnOnce()) {
+    let start = Instant::now();
+    f();
+    println!("{name:<40} {:>10.2?}", start.elapsed());
+}
+
+fn main() {
+    let n = 1_000_000;
+    let m = 4_000_000;
+    let mut rand = XorShift::new(0);
+    let mut adj = vec![vec![]; n];
+    for _ in 0..m {
+        let (u, v, w) = (rand.next() as usize % n, rand.next() as usize % n, rand.next() % 1_000_000_000);
+        adj[u].push((v, w));
+    }
+
+    bench("Dijkstra BinaryHeap", || {
+        let mut dist = vec![u64::MAX; n];
+        let mut heap = BinaryHeap::new();
+        dist[0] = 0;
+        heap.push(Reverse((0, 0)));
+        while let Some(Reverse((d, u))) = heap.pop() {
+            if d != dist[u] {
+                continue;
+            }
+            for &(v, w) in &adj[u] {
+                if d + w < dist[v] {
+                    dist[v] = d + w;
+                    heap.push(Reverse((d + w, v)));
+                }
+            }
+        }
+        black_box(dist);
+    });
+    bench("Dijkstra RadixHeap", || {
+        let mut dist = vec![u64::MAX; n];
+        let mut heap = RadixHeap::new();
+        dist[0] = 0;
+        heap.push(0, 0);
+        while let Some((d, u)) = heap.pop() {
+            if d != dist[u] {
+                continue;
+            }
+            for &(v, w) in &adj[u] {
+                if d + w < dist[v] {
+                    dist[v] = d + w;
+                    heap.push(d + w, v);
+                }
+            }
+        }
+        black_box(dist);
+    });
+}
@@ -0,0 +1,48 @@
+use std::{hint::black_box, time::Instant};
+
+use algo::{
+    collections::{arena_treap::ArenaTreap, treap::Treap},
+    random::XorShift,
+};
+
+fn bench(name: &str, f: impl FnOnce()) {
+    let start = Instant::now();
+    f();
+    println!("{name:<40} {:>10.2?}", start.elapsed());
+}
+
+fn main() {
+    let n = 1_000_000;
+    let mut rand = XorShift::new(0);
+    let random_keys = (0..n).map(|_| rand.next()).collect::<Vec<_>>();
+    let sorted_keys = (0..n as u64).collect::<Vec<_>>();
+
+    for (keys_name, keys) in [("random", &random_keys), ("sorted", &sorted_keys)] {
+        bench(&format!("Treap insert+contains+erase {keys_name}"), || {
+            let mut treap = Treap::new();
+            for &key in keys {
+                treap.insert(key);
+            }
+            for &key in keys {
+                black_box(treap.contains(&key));
+            }
+            for &key in keys {
+                treap.erase(&key);
+            }
+            black_box(treap.len());
+        });
+        bench(&format!("ArenaTreap insert+contains+erase {keys_name}"), || {
+            let mut treap = ArenaTreap::with_capacity(n);
+            for &key in keys {
+                treap.insert(key);
+            }
+            for &key in keys {
+                black_box(treap.contains(&key));
+            }
+            for &key in keys {
+                treap.erase(&key);
+            }
+            black_box(treap.len());
+        });
+    }
+}
@@ -0,0 +1,529 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::super::random::rand_u64;
+
+const NIL: u32 = u32::MAX;
+
+struct ArenaNode<T: PartialOrd> {
+    key: T,
+    left: u32,
+    right: u32,
+    priority: u64,
+    len: u32,
+}
+
+/// Where a subtree hangs, either the root of the current operation or a child of a node
+#[derive(Clone, Copy)]
+enum Slot {
+    Root,
+    Left(u32),
+    Right(u32),
+}
+
+// source of ArenaTreap ids, so that an ArenaRoot can't be used with another treap
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Tree detached from the main tree of an `ArenaTreap` by `split`, its nodes stay in the arena of that treap.
+/// Its keys are dropped by `discard` or together with the treap.
+#[must_use]
+pub struct ArenaRoot {
+    treap_id: u64,
+    root: u32,
+}
+
+/// Treap storing its nodes in a `Vec` and linking them by `u32` indices.
+/// All the operations are iterative, so they can't overflow the stack however unbalanced the tree gets.
+/// `split` and `merge` only relink nodes, the part split off stays in the same arena as an `ArenaRoot`.
+// if key == other_key then it will be in the left subtree
+pub struct ArenaTreap<T: PartialOrd> {
+    id: u64,
+    // None for free slots, so that removed keys are dropped right away
+    nodes: Vec<Option<ArenaNode<T>>>,
+    free: Vec<u32>,
+    root: u32,
+    // reused by split and merge to avoid allocating a path on every operation
+    path: Vec<u32>,
+}
+
+impl<T: Clone + PartialOrd> Default for ArenaTreap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PartialOrd> ArenaTreap<T> {
+    fn node(&self, i: u32) -> &ArenaNode<T> {
+        self.nodes[i as usize].as_ref().unwrap()
+    }
+}
+
+impl<T: Clone + PartialOrd> ArenaTreap<T> {
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            nodes: vec![],
+            free: vec![],
+            root: NIL,
+            path: vec![],
+        }
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            nodes: Vec::with_capacity(capacity),
+            free: vec![],
+            root: NIL,
+            path: vec![],
+        }
+    }
+
+    fn node_mut(&mut self, i: u32) -> &mut ArenaNode<T> {
+        self.nodes[i as usize].as_mut().unwrap()
+    }
+    fn node_len(&self, i: u32) -> u32 {
+        if i == NIL { 0 } else { self.node(i).len }
+    }
+    fn recompute(&mut self, i: u32) {
+        let len = self.node_len(self.node(i).left) + self.node_len(self.node(i).right) + 1;
+        self.node_mut(i).len = len;
+    }
+    fn recompute_path(&mut self, path: &[u32]) {
+        for &i in path.iter().rev() {
+            self.recompute(i);
+        }
+    }
+    fn take_path(&mut self) -> Vec<u32> {
+        let mut path = std::mem::take(&mut self.path);
+        path.clear();
+        path
+    }
+    fn set_slot(&mut self, slot: Slot, root: &mut u32, val: u32) {
+        match slot {
+            Slot::Root => *root = val,
+            Slot::Left(i) => self.node_mut(i).left = val,
+            Slot::Right(i) => self.node_mut(i).right = val,
+        }
+    }
+    fn alloc(&mut self, key: T) -> u32 {
+        let node = ArenaNode {
+            key,
+            left: NIL,
+            right: NIL,
+            priority: rand_u64(),
+            len: 1,
+        };
+        if let Some(i) = self.free.pop() {
+            self.nodes[i as usize] = Some(node);
+            i
+        } else {
+            assert!(self.nodes.len() < NIL as usize, "ArenaTreap can hold at most u32::MAX - 1 nodes");
+            self.nodes.push(Some(node));
+            (self.nodes.len()-1) as u32
+        }
+    }
+    fn release(&mut self, i: u32) {
+        self.nodes[i as usize] = None;
+        self.free.push(i);
+    }
+
+    /// Splits the subtree `root` into keys `<= x` and `> x`
+    fn split_node(&mut self, root: u32, x: &T) -> (u32, u32) {
+        let (mut left_root, mut right_root) = (NIL, NIL);
+        let (mut left_slot, mut right_slot) = (Slot::Root, Slot::Root);
+        let mut path = self.take_path();
+        let mut cur = root;
+        while cur != NIL {
+            path.push(cur);
+            if self.node(cur).key > *x {
+                self.set_slot(right_slot, &mut right_root, cur);
+                right_slot = Slot::Left(cur);
+                cur = self.node(cur).left;
+            } else {
+                self.set_slot(left_slot, &mut left_root, cur);
+                left_slot = Slot::Right(cur);
+                cur = self.node(cur).right;
+            }
+        }
+        self.set_slot(left_slot, &mut left_root, NIL);
+        self.set_slot(right_slot, &mut right_root, NIL);
+        self.recompute_path(&path);
+        self.path = path;
+        (left_root, right_root)
+    }
+
+    /// All the keys in `left` must be lower than all the keys in `right`
+    fn merge_node(&mut self, mut left: u32, mut right: u32) -> u32 {
+        let mut root = NIL;
+        let mut slot = Slot::Root;
+        let mut path = self.take_path();
+        while left != NIL && right != NIL {
+            if self.node(left).priority >= self.node(right).priority {
+                self.set_slot(slot, &mut root, left);
+                path.push(left);
+                slot = Slot::Right(left);
+                left = self.node(left).right;
+            } else {
+                self.set_slot(slot, &mut root, right);
+                path.push(right);
+                slot = Slot::Left(right);
+                right = self.node(right).left;
+            }
+        }
+        self.set_slot(slot, &mut root, if left != NIL { left } else { right });
+        self.recompute_path(&path);
+        self.path = path;
+        root
+    }
+
+    pub fn insert(&mut self, key: T) {
+        let new = self.alloc(key);
+        let priority = self.node(new).priority;
+        let mut slot = Slot::Root;
+        let mut cur = self.root;
+        while cur != NIL && self.node(cur).priority > priority {
+            self.node_mut(cur).len += 1;
+            if self.node(new).key <= self.node(cur).key {
+                slot = Slot::Left(cur);
+                cur = self.node(cur).left;
+            } else {
+                slot = Slot::Right(cur);
+                cur = self.node(cur).right;
+            }
+        }
+        let key = self.node(new).key.clone();
+        let (left, right) = self.split_node(cur, &key);
+        self.node_mut(new).left = left;
+        self.node_mut(new).right = right;
+        self.recompute(new);
+        let mut root = self.root;
+        self.set_slot(slot, &mut root, new);
+        self.root = root;
+    }
+
+    /// Removes one occurrence of `key`
+    pub fn erase(&mut self, key: &T) {
+        if !self.contains(key) {
+            return;
+        }
+        let mut slot = Slot::Root;
+        let mut cur = self.root;
+        while cur != NIL {
+            match key.partial_cmp(&self.node(cur).key).unwrap() {
+                std::cmp::Ordering::Less => {
+                    self.node_mut(cur).len -= 1;
+                    slot = Slot::Left(cur);
+                    cur = self.node(cur).left;
+                }
+                std::cmp::Ordering::Greater => {
+                    self.node_mut(cur).len -= 1;
+                    slot = Slot::Right(cur);
+                    cur = self.node(cur).right;
+                }
+                std::cmp::Ordering::Equal => break,
+            }
+        }
+        let merged = self.merge_node(self.node(cur).left, self.node(cur).right);
+        let mut root = self.root;
+        self.set_slot(slot, &mut root, merged);
+        self.root = root;
+        self.release(cur);
+    }
+
+    pub fn contains(&self, key: &T) -> bool {
+        let mut cur = self.root;
+        while cur != NIL {
+            match key.partial_cmp(&self.node(cur).key).unwrap() {
+                std::cmp::Ordering::Less => cur = self.node(cur).left,
+                std::cmp::Ordering::Greater => cur = self.node(cur).right,
+                std::cmp::Ordering::Equal => return true,
+            }
+        }
+        false
+    }
+
+    fn check_root(&self, root: &ArenaRoot) {
+        assert_eq!(root.treap_id, self.id, "ArenaRoot belongs to another ArenaTreap");
+    }
+
+    /// Splits off the keys higher than `x` in O(log n), only the keys smaller than or equal to `x` remain in the treap.
+    pub fn split(&mut self, x: &T) -> ArenaRoot {
+        let (left, right) = self.split_node(self.root, x);
+        self.root = left;
+        ArenaRoot { treap_id: self.id, root: right }
+    }
+
+    /// Appends a tree split off this treap in O(log n), all its keys must be higher than all the keys in the treap.
+    pub fn merge(&mut self, other: ArenaRoot) {
+        self.check_root(&other);
+        self.root = self.merge_node(self.root, other.root);
+    }
+
+    /// Makes `other` the tree of the treap and returns the previous one detached, e.g. to query the part split off
+    pub fn swap_root(&mut self, other: ArenaRoot) -> ArenaRoot {
+        self.check_root(&other);
+        let prev = std::mem::replace(&mut self.root, other.root);
+        ArenaRoot { treap_id: self.id, root: prev }
+    }
+
+    /// Removes all the keys of a detached tree in O(its size)
+    pub fn discard(&mut self, root: ArenaRoot) {
+        self.check_root(&root);
+        let mut stack = vec![root.root];
+        while let Some(i) = stack.pop() {
+            if i != NIL {
+                stack.push(self.node(i).left);
+                stack.push(self.node(i).right);
+                self.release(i);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.node_len(self.root) as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns the k-th smallest key, 0-indexed
+    pub fn kth(&self, mut k: usize) -> Option<&T> {
+        let mut cur = self.root;
+        while cur != NIL {
+            let left_len = self.node_len(self.node(cur).left) as usize;
+            if k < left_len {
+                cur = self.node(cur).left;
+            } else if k == left_len {
+                return Some(&self.node(cur).key);
+            } else {
+                k -= left_len + 1;
+                cur = self.node(cur).right;
+            }
+        }
+        None
+    }
+    /// Returns the number of keys smaller than `key`
+    pub fn rank(&self, key: &T) -> usize {
+        let mut result = 0;
+        let mut cur = self.root;
+        while cur != NIL {
+            if self.node(cur).key < *key {
+                result += self.node_len(self.node(cur).left) as usize + 1;
+                cur = self.node(cur).right;
+            } else {
+                cur = self.node(cur).left;
+            }
+        }
+        result
+    }
+    /// Returns the smallest key for which `pred` is false, `pred` must be true for a prefix of the keys
+    fn first_not(&self, pred: impl Fn(&T) -> bool) -> Option<&T> {
+        let mut result = None;
+        let mut cur = self.root;
+        while cur != NIL {
+            if pred(&self.node(cur).key) {
+                cur = self.node(cur).right;
+            } else {
+                result = Some(&self.node(cur).key);
+                cur = self.node(cur).left;
+            }
+        }
+        result
+    }
+    /// Returns the largest key for which `pred` is true, `pred` must be true for a prefix of the keys
+    fn last(&self, pred: impl Fn(&T) -> bool) -> Option<&T> {
+        let mut result = None;
+        let mut cur = self.root;
+        while cur != NIL {
+            if pred(&self.node(cur).key) {
+                result = Some(&self.node(cur).key);
+                cur = self.node(cur).right;
+            } else {
+                cur = self.node(cur).left;
+            }
+        }
+        result
+    }
+    /// Returns the smallest key that is greater than or equal to `key`
+    pub fn lower_bound(&self, key: &T) -> Option<&T> {
+        self.first_not(|k| k < key)
+    }
+    /// Returns the smallest key that is greater than `key`
+    pub fn upper_bound(&self, key: &T) -> Option<&T> {
+        self.first_not(|k| k <= key)
+    }
+    /// Returns the largest key that is smaller than `key`
+    pub fn predecessor(&self, key: &T) -> Option<&T> {
+        self.last(|k| k < key)
+    }
+    /// Returns the smallest key that is greater than `key`, same as `upper_bound`
+    pub fn successor(&self, key: &T) -> Option<&T> {
+        self.upper_bound(key)
+    }
+    pub fn min(&self) -> Option<&T> {
+        self.kth(0)
+    }
+    pub fn max(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|k| self.kth(k))
+    }
+    /// Iterates over the keys in increasing order
+    pub fn iter(&self) -> ArenaTreapIter<'_, T> {
+        let mut iter = ArenaTreapIter { treap: self, stack: vec![] };
+        iter.push_left(self.root);
+        iter
+    }
+}
+
+pub struct ArenaTreapIter<'a, T: PartialOrd> {
+    treap: &'a ArenaTreap<T>,
+    stack: Vec<u32>,
+}
+
+impl<T: PartialOrd> ArenaTreapIter<'_, T> {
+    fn push_left(&mut self, mut cur: u32) {
+        while cur != NIL {
+            self.stack.push(cur);
+            cur = self.treap.node(cur).left;
+        }
+    }
+}
+
+impl<'a, T: PartialOrd> Iterator for ArenaTreapIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.stack.pop()?;
+        let treap = self.treap;
+        self.push_left(treap.node(i).right);
+        Some(&treap.node(i).key)
+    }
+}
+
+impl<'a, T: Clone + PartialOrd> IntoIterator for &'a ArenaTreap<T> {
+    type Item = &'a T;
+    type IntoIter = ArenaTreapIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::XorShift;
+
+    use super::*;
+
+    #[test]
+    fn test_split_merge() {
+        let mut treap: ArenaTreap<i32> = ArenaTreap::new();
+        for key in [10, 5, 15, 7, 12] {
+            treap.insert(key);
+        }
+
+        let right = treap.split(&10);
+        assert_eq!(treap.iter().copied().collect::<Vec<_>>(), vec![5, 7, 10]);
+        let left = treap.swap_root(right);
+        assert_eq!(treap.iter().copied().collect::<Vec<_>>(), vec![12, 15]);
+        let right = treap.swap_root(left);
+
+        treap.merge(right);
+        assert_eq!(treap.len(), 5);
+        assert!(treap.contains(&12));
+        assert_eq!(treap.kth(3), Some(&12));
+
+        let right = treap.split(&7);
+        treap.discard(right);
+        treap.insert(6);
+        assert_eq!(treap.iter().copied().collect::<Vec<_>>(), vec![5, 6, 7]);
+        assert_eq!(treap.nodes.len(), 5);
+        assert_eq!(treap.nodes.iter().filter(|node| node.is_some()).count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "another ArenaTreap")]
+    fn test_root_of_other_treap() {
+        let mut a: ArenaTreap<i32> = ArenaTreap::new();
+        let mut b: ArenaTreap<i32> = ArenaTreap::new();
+        b.insert(1);
+        let root = b.split(&0);
+        a.merge(root);
+    }
+
+    #[test]
+    fn test_many_split_merge() {
+        let n = 100_000;
+        let mut rand = XorShift::new(12);
+        let mut treap = ArenaTreap::with_capacity(n);
+        for key in 0..n as u64 {
+            treap.insert(key);
+        }
+        // O(n) per split or merge would take ~1e10 steps
+        for _ in 0..n {
+            let x = rand.next() % n as u64;
+            let right = treap.split(&x);
+            assert_eq!(treap.len(), x as usize + 1);
+            treap.merge(right);
+        }
+        assert_eq!(treap.len(), n);
+        assert_eq!(treap.nodes.len(), n);
+        assert!(treap.iter().copied().eq(0..n as u64));
+    }
+
+    #[test]
+    fn test_sorted_inserts() {
+        let n = 1_000_000;
+        let mut treap = ArenaTreap::with_capacity(n);
+        for key in 0..n as u32 {
+            treap.insert(key);
+        }
+        assert_eq!(treap.len(), n);
+        assert_eq!(treap.rank(&1234), 1234);
+        for key in (0..n as u32).step_by(2) {
+            treap.erase(&key);
+        }
+        assert_eq!(treap.len(), n/2);
+        assert_eq!(treap.min(), Some(&1));
+        assert_eq!(treap.nodes.len(), n);
+    }
+
+    #[test]
+    fn test_brute() {
+        let mut rand = XorShift::new(11);
+        let mut treap: ArenaTreap<u64> = ArenaTreap::new();
+        let mut keys = vec![];
+        for _ in 0..2000 {
+            let key = rand.next() % 300;
+            if rand.next().is_multiple_of(3) {
+                treap.erase(&key);
+                if let Some(pos) = keys.iter().position(|k| *k == key) {
+                    keys.remove(pos);
+                }
+            } else {
+                treap.insert(key);
+                keys.push(key);
+            }
+            keys.sort();
+
+            assert_eq!(treap.len(), keys.len());
+            assert_eq!(treap.contains(&key), keys.contains(&key));
+            let x = rand.next() % 300;
+            assert_eq!(treap.rank(&x), keys.iter().filter(|k| **k < x).count());
+            assert_eq!(treap.lower_bound(&x), keys.iter().find(|k| **k >= x));
+            assert_eq!(treap.predecessor(&x), keys.iter().rev().find(|k| **k < x));
+        }
+        assert_eq!(treap.iter().copied().collect::<Vec<_>>(), keys);
+    }
+
+    #[test]
+    fn test_same_seed_same_structure() {
+        let build = || {
+            let mut treap = ArenaTreap::new();
+            for key in 0..1000u64 {
+                treap.insert(key * 7919 % 1000);
+            }
+            treap.nodes.iter().flatten().map(|node| (node.key, node.left, node.right, node.priority)).collect::<Vec<_>>()
+        };
+        let first = crate::random::with_seed(42, build);
+        assert_eq!(first, crate::random::with_seed(42, build));
+        assert_ne!(first, crate::random::with_seed(43, build));
+    }
+}
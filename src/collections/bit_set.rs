@@ -0,0 +1,337 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, ShlAssign, Shr, ShrAssign};
+
+/// Fixed size set of bits `0..len` with word-parallel operations, `<<` moves bits towards higher indices.
+/// Bits shifted past `len` are dropped, binary operations require equal lengths.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    // bits past len in the last word are always zero
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    pub fn new_from_iter(iter: impl IntoIterator<Item=bool>) -> Self {
+        let bits = iter.into_iter().collect::<Vec<_>>();
+        let mut result = Self::new(bits.len());
+        for (i, bit) in bits.into_iter().enumerate() {
+            result.set(i, bit);
+        }
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The underlying words, bit `i` is bit `i % 64` of word `i / 64`
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    fn trim(&mut self) {
+        if !self.len.is_multiple_of(64) {
+            *self.words.last_mut().unwrap() &= (1 << (self.len % 64)) - 1;
+        }
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len);
+        self.words[i/64] >> (i%64) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, val: bool) {
+        assert!(i < self.len);
+        if val {
+            self.words[i/64] |= 1 << (i%64);
+        }
+        else {
+            self.words[i/64] &= !(1 << (i%64));
+        }
+    }
+
+    pub fn flip(&mut self, i: usize) {
+        assert!(i < self.len);
+        self.words[i/64] ^= 1 << (i%64);
+    }
+
+    /// Sets all bits to `val`
+    pub fn fill(&mut self, val: bool) {
+        self.words.fill(if val { u64::MAX } else { 0 });
+        self.trim();
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns true if any bit is set
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|&w| w != 0)
+    }
+
+    /// First set bit at index `i` or later
+    fn find_from(&self, i: usize) -> Option<usize> {
+        if i >= self.len {
+            return None;
+        }
+        let mut w = i/64;
+        let mut word = self.words[w] & (u64::MAX << (i%64));
+        loop {
+            if word != 0 {
+                return Some(w*64 + word.trailing_zeros() as usize);
+            }
+            w += 1;
+            word = *self.words.get(w)?;
+        }
+    }
+
+    pub fn find_first(&self) -> Option<usize> {
+        self.find_from(0)
+    }
+
+    /// First set bit strictly after `i`
+    pub fn find_next(&self, i: usize) -> Option<usize> {
+        self.find_from(i+1)
+    }
+
+    /// Iterates over the indices of set bits in increasing order
+    pub fn iter(&self) -> BitSetIter<'_> {
+        BitSetIter { set: self, next: self.find_first() }
+    }
+}
+
+pub struct BitSetIter<'a> {
+    set: &'a BitSet,
+    next: Option<usize>,
+}
+
+impl Iterator for BitSetIter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let result = self.next?;
+        self.next = self.set.find_next(result);
+        Some(result)
+    }
+}
+
+impl<'a> IntoIterator for &'a BitSet {
+    type Item = usize;
+    type IntoIter = BitSetIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl ShlAssign<usize> for BitSet {
+    fn shl_assign(&mut self, k: usize) {
+        let (ws, bs) = (k/64, k%64);
+        let n = self.words.len();
+        for i in (0..n).rev() {
+            let mut word = 0;
+            if i >= ws {
+                word = self.words[i-ws] << bs;
+                if bs > 0 && i > ws {
+                    word |= self.words[i-ws-1] >> (64-bs);
+                }
+            }
+            self.words[i] = word;
+        }
+        self.trim();
+    }
+}
+
+impl ShrAssign<usize> for BitSet {
+    fn shr_assign(&mut self, k: usize) {
+        let (ws, bs) = (k/64, k%64);
+        let n = self.words.len();
+        for i in 0..n {
+            let mut word = 0;
+            if i+ws < n {
+                word = self.words[i+ws] >> bs;
+                if bs > 0 && i+ws+1 < n {
+                    word |= self.words[i+ws+1] << (64-bs);
+                }
+            }
+            self.words[i] = word;
+        }
+    }
+}
+
+impl Shl<usize> for &BitSet {
+    type Output = BitSet;
+
+    fn shl(self, k: usize) -> BitSet {
+        let mut result = self.clone();
+        result <<= k;
+        result
+    }
+}
+
+impl Shr<usize> for &BitSet {
+    type Output = BitSet;
+
+    fn shr(self, k: usize) -> BitSet {
+        let mut result = self.clone();
+        result >>= k;
+        result
+    }
+}
+
+impl Shl<usize> for BitSet {
+    type Output = BitSet;
+
+    fn shl(mut self, k: usize) -> BitSet {
+        self <<= k;
+        self
+    }
+}
+
+impl Shr<usize> for BitSet {
+    type Output = BitSet;
+
+    fn shr(mut self, k: usize) -> BitSet {
+        self >>= k;
+        self
+    }
+}
+
+impl Not for &BitSet {
+    type Output = BitSet;
+
+    fn not(self) -> BitSet {
+        let mut result = self.clone();
+        result.words.iter_mut().for_each(|w| *w = !*w);
+        result.trim();
+        result
+    }
+}
+
+impl Not for BitSet {
+    type Output = BitSet;
+
+    fn not(self) -> BitSet {
+        !&self
+    }
+}
+
+macro_rules! impl_bit_op {
+    ($op: ident, $f: ident, $op_assign: ident, $f_assign: ident, $e: tt) => {
+        impl $op_assign<&BitSet> for BitSet {
+            fn $f_assign(&mut self, rhs: &BitSet) {
+                assert_eq!(self.len, rhs.len, "bit sets of different lengths");
+                for (a, b) in self.words.iter_mut().zip(&rhs.words) {
+                    *a = *a $e *b;
+                }
+            }
+        }
+
+        impl $op_assign<BitSet> for BitSet {
+            fn $f_assign(&mut self, rhs: BitSet) {
+                *self = &*self $e &rhs;
+            }
+        }
+
+        impl $op<&BitSet> for &BitSet {
+            type Output = BitSet;
+
+            fn $f(self, rhs: &BitSet) -> BitSet {
+                let mut result = self.clone();
+                result.$f_assign(rhs);
+                result
+            }
+        }
+
+        impl $op<BitSet> for BitSet {
+            type Output = BitSet;
+
+            fn $f(mut self, rhs: BitSet) -> BitSet {
+                self.$f_assign(&rhs);
+                self
+            }
+        }
+    };
+}
+
+impl_bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+impl_bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+impl_bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    fn to_vec(set: &BitSet) -> Vec<bool> {
+        (0..set.len()).map(|i| set.get(i)).collect()
+    }
+
+    #[test]
+    fn brute() {
+        let mut rand = random::XorShift::new(21);
+        for len in [0, 1, 63, 64, 65, 200] {
+            let a = (0..len).map(|_| rand.next().is_multiple_of(3)).collect::<Vec<_>>();
+            let b = (0..len).map(|_| rand.next().is_multiple_of(2)).collect::<Vec<_>>();
+            let (sa, sb) = (BitSet::new_from_iter(a.clone()), BitSet::new_from_iter(b.clone()));
+            assert_eq!(to_vec(&sa), a);
+            assert_eq!(sa.count_ones(), a.iter().filter(|x| **x).count());
+            assert_eq!(sa.iter().collect::<Vec<_>>(), (0..len).filter(|&i| a[i]).collect::<Vec<_>>());
+            assert_eq!(sa.find_first(), a.iter().position(|x| *x));
+            for i in 0..len {
+                assert_eq!(sa.find_next(i), (i+1..len).find(|&j| a[j]));
+            }
+
+            assert_eq!(to_vec(&(&sa & &sb)), (0..len).map(|i| a[i] & b[i]).collect::<Vec<_>>());
+            assert_eq!(to_vec(&(&sa | &sb)), (0..len).map(|i| a[i] | b[i]).collect::<Vec<_>>());
+            assert_eq!(to_vec(&(sa.clone() ^ sb.clone())), (0..len).map(|i| a[i] ^ b[i]).collect::<Vec<_>>());
+            assert_eq!(to_vec(&!&sa), a.iter().map(|x| !x).collect::<Vec<_>>());
+            assert_eq!((!&sa).count_ones(), len - sa.count_ones());
+
+            for k in [0, 1, 5, 63, 64, 65, 130, 250] {
+                assert_eq!(to_vec(&(&sa << k)), (0..len).map(|i| i >= k && a[i-k]).collect::<Vec<_>>());
+                assert_eq!(to_vec(&(&sa >> k)), (0..len).map(|i| i+k < len && a[i+k]).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn subset_sum() {
+        let weights = [3, 5, 7, 11, 64, 100];
+        let max = 300;
+        let mut dp = BitSet::new(max+1);
+        dp.set(0, true);
+        for &w in &weights {
+            dp |= &dp << w;
+        }
+        for s in 0..=max {
+            let reachable = (0..1 << weights.len()).any(|mask: usize| {
+                (0..weights.len()).filter(|&i| mask >> i & 1 == 1).map(|i| weights[i]).sum::<usize>() == s
+            });
+            assert_eq!(dp.get(s), reachable);
+        }
+    }
+
+    #[test]
+    fn fill_and_flip() {
+        let mut set = BitSet::new(70);
+        set.fill(true);
+        assert_eq!(set.count_ones(), 70);
+        set.flip(69);
+        assert!(!set.get(69));
+        set.fill(false);
+        assert!(!set.any());
+        assert_eq!(set.find_first(), None);
+    }
+}
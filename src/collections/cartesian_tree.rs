@@ -0,0 +1,186 @@
+use std::ops::{Range, RangeBounds};
+
+use super::{sparse_table::LinearRmq, to_range};
+
+/// Binary tree over the indices of a slice, in-order traversal gives `0..n` and every node is the minimum (or maximum) of its subtree.
+/// Of equal values the leftmost one is the ancestor.
+pub struct CartesianTree {
+    pub root: Option<usize>,
+    pub parent: Vec<Option<usize>>,
+    pub left: Vec<Option<usize>>,
+    pub right: Vec<Option<usize>>,
+}
+
+impl CartesianTree {
+    pub fn new_min<T: Ord>(values: &[T]) -> Self {
+        Self::new(values, |a, b| a > b)
+    }
+
+    pub fn new_max<T: Ord>(values: &[T]) -> Self {
+        Self::new(values, |a, b| a < b)
+    }
+
+    /// Builds the tree in O(n), `below(a, b)` is true if `a` has to be a descendant of a later `b`
+    fn new<T>(values: &[T], below: impl Fn(&T, &T) -> bool) -> Self {
+        let n = values.len();
+        let mut parent = vec![None; n];
+        let mut left = vec![None; n];
+        let mut right = vec![None; n];
+        // right spine of the tree built so far
+        let mut stack: Vec<usize> = vec![];
+        for i in 0..n {
+            let mut last = None;
+            while let Some(&top) = stack.last() {
+                if !below(&values[top], &values[i]) {
+                    break;
+                }
+                last = stack.pop();
+            }
+            if let Some(last) = last {
+                left[i] = Some(last);
+                parent[last] = Some(i);
+            }
+            if let Some(&top) = stack.last() {
+                right[top] = Some(i);
+                parent[i] = Some(top);
+            }
+            stack.push(i);
+        }
+        Self {
+            root: stack.first().copied(),
+            parent,
+            left,
+            right,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+}
+
+/// Range minimum query with O(n) preprocessing and O(1) query, as the lca of the range ends in the Cartesian tree.
+/// The lca is found by a range minimum over depths in the euler tour of the tree.
+pub struct CartesianRmq<T: Ord + Clone> {
+    values: Vec<T>,
+    // first[i] = first position of node i in the tour
+    first: Vec<usize>,
+    tour: Vec<usize>,
+    depths: LinearRmq<usize>,
+}
+
+impl<T: Ord + Clone> CartesianRmq<T> {
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>) -> Self {
+        let values = iter.into_iter().collect::<Vec<_>>();
+        let tree = CartesianTree::new_min(&values);
+        let n = values.len();
+        let mut first = vec![0; n];
+        let mut tour = Vec::with_capacity((2*n).saturating_sub(1));
+        let mut depth = Vec::with_capacity(tour.capacity());
+        // (node, depth, number of children visited)
+        let mut stack = tree.root.map(|root| (root, 0, 0)).into_iter().collect::<Vec<_>>();
+        while let Some((v, d, visited)) = stack.pop() {
+            if visited == 0 {
+                first[v] = tour.len();
+            }
+            tour.push(v);
+            depth.push(d);
+            let children = [tree.left[v], tree.right[v]];
+            if let Some((i, child)) = children.iter().enumerate().skip(visited).find_map(|(i, c)| c.map(|c| (i, c))) {
+                stack.push((v, d, i+1));
+                stack.push((child, d+1, 0));
+            }
+        }
+        Self {
+            values,
+            first,
+            tour,
+            depths: LinearRmq::new_from_iter(depth),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the index of the minimum in the range, the leftmost one on ties, `None` for an empty range
+    pub fn argmin(&self, range: impl RangeBounds<usize>) -> Option<usize> {
+        let Range { start: l, end: r } = to_range(range, self.values.len());
+        if l >= r {
+            return None;
+        }
+        let (a, b) = (self.first[l], self.first[r-1]);
+        let pos = self.depths.argmin(a.min(b)..=a.max(b))?;
+        Some(self.tour[pos])
+    }
+
+    /// Returns the minimum in the range, `None` for an empty range
+    pub fn query(&self, range: impl RangeBounds<usize>) -> Option<T> {
+        self.argmin(range).map(|i| self.values[i].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    fn in_order(tree: &CartesianTree, v: Option<usize>, out: &mut Vec<usize>) {
+        if let Some(v) = v {
+            in_order(tree, tree.left[v], out);
+            out.push(v);
+            in_order(tree, tree.right[v], out);
+        }
+    }
+
+    #[test]
+    fn tree_shape() {
+        let mut rand = random::XorShift::new(24);
+        for n in [0, 1, 2, 10, 100] {
+            let arr = (0..n).map(|_| rand.next() % 10).collect::<Vec<_>>();
+            for (tree, is_min) in [(CartesianTree::new_min(&arr), true), (CartesianTree::new_max(&arr), false)] {
+                let mut order = vec![];
+                in_order(&tree, tree.root, &mut order);
+                assert_eq!(order, (0..n).collect::<Vec<_>>());
+                for v in 0..n {
+                    match tree.parent[v] {
+                        Some(p) => {
+                            assert!(tree.left[p] == Some(v) || tree.right[p] == Some(v));
+                            assert!(if is_min { arr[p] <= arr[v] } else { arr[p] >= arr[v] });
+                            if arr[p] == arr[v] {
+                                assert!(p < v);
+                            }
+                        }
+                        None => assert_eq!(tree.root, Some(v)),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rmq_brute() {
+        let mut rand = random::XorShift::new(25);
+        for n in [1, 2, 65, 300] {
+            let arr = (0..n).map(|_| rand.next() % 20).collect::<Vec<_>>();
+            let rmq = CartesianRmq::new_from_iter(arr.clone());
+            for l in 0..n {
+                for r in l+1..=n {
+                    let mn = *arr[l..r].iter().min().unwrap();
+                    assert_eq!(rmq.argmin(l..r), Some(l + arr[l..r].iter().position(|x| *x == mn).unwrap()));
+                    assert_eq!(rmq.query(l..r), Some(mn));
+                }
+            }
+            assert_eq!(rmq.query(3..3), None);
+        }
+        assert_eq!(CartesianRmq::new_from_iter(Vec::<i32>::new()).query(..), None);
+    }
+}
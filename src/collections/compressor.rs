@@ -0,0 +1,163 @@
+use std::ops::{Bound, Range, RangeBounds};
+
+/// Maps a set of values to `0..len` preserving order, for building index based structures over large or sparse values
+pub struct Compressor<T: Ord + Clone> {
+    values: Vec<T>,
+}
+
+impl<T: Ord + Clone> Compressor<T> {
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>) -> Self {
+        let mut values = iter.into_iter().collect::<Vec<_>>();
+        values.sort();
+        values.dedup();
+        Self { values }
+    }
+
+    /// Number of distinct values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The distinct values in increasing order
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Index of `v`, `None` if it was not collected
+    pub fn index(&self, v: &T) -> Option<usize> {
+        self.values.binary_search(v).ok()
+    }
+
+    pub fn value(&self, i: usize) -> &T {
+        &self.values[i]
+    }
+
+    /// Index of the first value not less than `v`
+    pub fn lower_bound(&self, v: &T) -> usize {
+        self.values.partition_point(|x| x < v)
+    }
+
+    /// Index of the first value greater than `v`
+    pub fn upper_bound(&self, v: &T) -> usize {
+        self.values.partition_point(|x| x <= v)
+    }
+
+    /// Indices of the collected values within `range`, to be passed to e.g. `SegTree::query`
+    pub fn index_range(&self, range: impl RangeBounds<T>) -> Range<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(v) => self.lower_bound(v),
+            Bound::Excluded(v) => self.upper_bound(v),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(v) => self.upper_bound(v),
+            Bound::Excluded(v) => self.lower_bound(v),
+            Bound::Unbounded => self.values.len(),
+        };
+        start..end.max(start)
+    }
+
+    /// Replaces every value by its index, all values have to be collected
+    pub fn compress<'a>(&'a self, iter: impl IntoIterator<Item=&'a T> + 'a) -> impl Iterator<Item=usize> + 'a {
+        iter.into_iter().map(|v| self.index(v).expect("value was not collected"))
+    }
+}
+
+/// Compresses both coordinates of a set of points independently
+pub struct Compressor2D<X: Ord + Clone, Y: Ord + Clone> {
+    xs: Compressor<X>,
+    ys: Compressor<Y>,
+}
+
+impl<X: Ord + Clone, Y: Ord + Clone> Compressor2D<X, Y> {
+    pub fn new_from_iter(iter: impl IntoIterator<Item=(X, Y)>) -> Self {
+        let (xs, ys): (Vec<_>, Vec<_>) = iter.into_iter().unzip();
+        Self {
+            xs: Compressor::new_from_iter(xs),
+            ys: Compressor::new_from_iter(ys),
+        }
+    }
+
+    pub fn xs(&self) -> &Compressor<X> {
+        &self.xs
+    }
+
+    pub fn ys(&self) -> &Compressor<Y> {
+        &self.ys
+    }
+
+    /// Grid dimensions, number of distinct xs and ys
+    pub fn dims(&self) -> (usize, usize) {
+        (self.xs.len(), self.ys.len())
+    }
+
+    /// Grid cell of the point, `None` if a coordinate was not collected
+    pub fn index(&self, (x, y): (&X, &Y)) -> Option<(usize, usize)> {
+        Some((self.xs.index(x)?, self.ys.index(y)?))
+    }
+
+    pub fn value(&self, (i, j): (usize, usize)) -> (&X, &Y) {
+        (self.xs.value(i), self.ys.value(j))
+    }
+
+    /// Grid rows and columns within the rectangle, to be passed to e.g. `SegTree2D::query`
+    pub fn index_ranges(&self, xs: impl RangeBounds<X>, ys: impl RangeBounds<Y>) -> (Range<usize>, Range<usize>) {
+        (self.xs.index_range(xs), self.ys.index_range(ys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{collections::fenwick_tree::FenwickTree2D, random};
+
+    #[test]
+    fn compress_1d() {
+        let mut rand = random::XorShift::new(22);
+        let arr = (0..200).map(|_| rand.next() % 1000 * 1_000_000_007).collect::<Vec<_>>();
+        let comp = Compressor::new_from_iter(arr.iter().copied());
+        let mut sorted = arr.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(comp.values(), &sorted[..]);
+
+        let indices = comp.compress(&arr).collect::<Vec<_>>();
+        for (i, &v) in arr.iter().enumerate() {
+            assert_eq!(*comp.value(indices[i]), v);
+        }
+        for _ in 0..500 {
+            let (a, b) = (rand.next() % 1100 * 1_000_000_007, rand.next() % 1100 * 1_000_000_007);
+            assert_eq!(comp.index(&a), sorted.binary_search(&a).ok());
+            assert_eq!(comp.lower_bound(&a), sorted.iter().filter(|x| **x < a).count());
+            assert_eq!(comp.upper_bound(&a), sorted.iter().filter(|x| **x <= a).count());
+            assert_eq!(comp.index_range(a..=b).len(), sorted.iter().filter(|x| (a..=b).contains(*x)).count());
+            assert_eq!(comp.index_range(a..b).len(), sorted.iter().filter(|x| (a..b).contains(*x)).count());
+        }
+        assert_eq!(comp.index_range(..), 0..comp.len());
+    }
+
+    #[test]
+    fn points_2d() {
+        let mut rand = random::XorShift::new(23);
+        let points = (0..100).map(|_| ((rand.next() % 50) as i64 - 25, rand.next() % 1_000_000)).collect::<Vec<_>>();
+        let comp = Compressor2D::new_from_iter(points.iter().copied());
+        let (n, m) = comp.dims();
+        let mut fenwick = FenwickTree2D::new(n, m);
+        for (x, y) in &points {
+            let (i, j) = comp.index((x, y)).unwrap();
+            assert_eq!(comp.value((i, j)), (x, y));
+            fenwick.add(i, j, 1);
+        }
+        for _ in 0..200 {
+            let (x1, x2) = ((rand.next() % 60) as i64 - 30, (rand.next() % 60) as i64 - 30);
+            let (y1, y2) = (rand.next() % 1_000_000, rand.next() % 1_000_000);
+            let (rows, cols) = comp.index_ranges(x1..=x2, y1..y2);
+            let expected = points.iter().filter(|(x, y)| (x1..=x2).contains(x) && (y1..y2).contains(y)).count();
+            assert_eq!(fenwick.query(rows, cols), expected as i64);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+use crate::math::Integer;
+
+use super::li_chao_tree::Line;
+
+/// Convex hull trick for minimum queries when lines are added in non-increasing order of slopes.
+/// For maximum insert negated lines and negate the result.
+pub struct MonotoneCht<T: Integer> {
+    lines: VecDeque<Line<T>>,
+}
+
+impl<T: Integer> Default for MonotoneCht<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Integer> MonotoneCht<T> {
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::new(),
+        }
+    }
+
+    /// Whether `mid` never attains the minimum, given slopes `left.a > mid.a > right.a`
+    fn is_redundant(left: &Line<T>, mid: &Line<T>, right: &Line<T>) -> bool {
+        let (a1, b1) = (left.a.to_i64() as i128, left.b.to_i64() as i128);
+        let (a2, b2) = (mid.a.to_i64() as i128, mid.b.to_i64() as i128);
+        let (a3, b3) = (right.a.to_i64() as i128, right.b.to_i64() as i128);
+        (b3-b1)*(a1-a2) <= (b2-b1)*(a1-a3)
+    }
+
+    /// Slope of `line` must not be bigger than the slope of any previously added line
+    pub fn add_line(&mut self, line: Line<T>) {
+        if let Some(last) = self.lines.back() {
+            assert!(line.a <= last.a, "lines have to be added in non-increasing order of slopes");
+            if line.a == last.a {
+                if line.b >= last.b {
+                    return;
+                }
+                self.lines.pop_back();
+            }
+        }
+        while self.lines.len() >= 2 && Self::is_redundant(&self.lines[self.lines.len()-2], &self.lines[self.lines.len()-1], &line) {
+            self.lines.pop_back();
+        }
+        self.lines.push_back(line);
+    }
+
+    /// Minimum over all lines at `x` in O(log n)
+    pub fn query(&self, x: T) -> Option<T> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        // values along the hull decrease and then increase
+        let (mut lo, mut hi) = (0, self.lines.len()-1);
+        while lo < hi {
+            let mid = (lo+hi)/2;
+            if self.lines[mid].eval(x) <= self.lines[mid+1].eval(x) {
+                hi = mid;
+            }
+            else {
+                lo = mid+1;
+            }
+        }
+        Some(Line::to_value(self.lines[lo].eval(x)))
+    }
+
+    /// Minimum over all lines at `x` in amortized O(1), `x` must not be smaller than in any previous call.
+    /// Drops the lines which can no longer be optimal, so it shouldn't be mixed with `query` for smaller `x`.
+    pub fn query_monotone(&mut self, x: T) -> Option<T> {
+        while self.lines.len() >= 2 && self.lines[1].eval(x) <= self.lines[0].eval(x) {
+            self.lines.pop_front();
+        }
+        self.lines.front().map(|line| Line::to_value(line.eval(x)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::XorShift;
+
+    use super::*;
+
+    fn random_lines(rand: &mut XorShift, cnt: usize) -> Vec<Line<i64>> {
+        let mut lines = (0..cnt)
+            .map(|_| Line::new((rand.next()%2001) as i64 - 1000, (rand.next()%2_000_001) as i64 - 1_000_000))
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|l| std::cmp::Reverse(l.a));
+        lines
+    }
+
+    #[test]
+    fn query_brute() {
+        let mut rand = XorShift::new(3);
+        let lines = random_lines(&mut rand, 300);
+        let mut cht = MonotoneCht::new();
+        for (i, &line) in lines.iter().enumerate() {
+            cht.add_line(line);
+            for x in -50..50 {
+                let x = x * 37;
+                let tar = lines[..=i].iter().map(|l| l.eval(x) as i64).min();
+                assert_eq!(cht.query(x), tar);
+            }
+        }
+    }
+
+    #[test]
+    fn query_monotone_brute() {
+        let mut rand = XorShift::new(4);
+        let lines = random_lines(&mut rand, 300);
+        let mut cht = MonotoneCht::new();
+        assert_eq!(cht.query_monotone(0), None);
+        for &line in &lines {
+            cht.add_line(line);
+        }
+        for x in -2000..2000 {
+            let tar = lines.iter().map(|l| l.eval(x) as i64).min();
+            assert_eq!(cht.query_monotone(x), tar);
+        }
+    }
+}
@@ -1,6 +1,9 @@
+use crate::math::AdditiveGroup;
+
 pub struct DisjointSet {
     data: Vec<usize>,
     size: Vec<usize>,
+    components: usize,
 }
 
 impl DisjointSet {
@@ -8,22 +11,23 @@ impl DisjointSet {
         Self {
             data: (0..n).collect(),
             size: vec![1; n],
+            components: n,
         }
     }
     pub fn find(&mut self, mut x: usize) -> usize {
-        if self.data[x] != x {
-            x = self.find(self.data[x]);
-            self.data[x] = x;
+        let root = self.find_ref(x);
+        while x != root {
+            let next = self.data[x];
+            self.data[x] = root;
+            x = next;
         }
-        x
+        root
     }
-    pub fn find_ref(&self, x: usize) -> usize {
-        if self.data[x] == x {
-            x
-        }
-        else {
-            self.find_ref(self.data[x])
+    pub fn find_ref(&self, mut x: usize) -> usize {
+        while self.data[x] != x {
+            x = self.data[x];
         }
+        x
     }
     pub fn join(&mut self, mut x: usize, mut y: usize) -> bool {
         x = self.find(x);
@@ -39,9 +43,13 @@ impl DisjointSet {
 
         self.size[x] += self.size[y];
         self.data[y] = x;
+        self.components -= 1;
 
         true
     }
+    pub fn same(&mut self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
     pub fn size(&mut self, x: usize) -> usize {
         let x = self.find(x);
         self.size[x]
@@ -49,4 +57,472 @@ impl DisjointSet {
     pub fn len(&self) -> usize {
         self.data.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    /// Adds a new node in its own component and returns its index
+    pub fn add_node(&mut self) -> usize {
+        self.data.push(self.data.len());
+        self.size.push(1);
+        self.components += 1;
+        self.data.len()-1
+    }
+    pub fn count_components(&self) -> usize {
+        self.components
+    }
+    /// Returns the components, each sorted and ordered by their smallest node
+    pub fn groups(&mut self) -> Vec<Vec<usize>> {
+        let mut index = vec![usize::MAX; self.len()];
+        let mut result: Vec<Vec<usize>> = vec![];
+        for x in 0..self.len() {
+            let root = self.find(x);
+            if index[root] == usize::MAX {
+                index[root] = result.len();
+                result.push(Vec::with_capacity(self.size[root]));
+            }
+            result[index[root]].push(x);
+        }
+        result
+    }
+}
+
+/// Disjoint set with a value attached to every component, `merge` is called with the values of the two joined components
+pub struct DisjointSetWithData<D, F: FnMut(D, D) -> D> {
+    dsu: DisjointSet,
+    data: Vec<Option<D>>,
+    merge: F,
+}
+
+impl<D, F: FnMut(D, D) -> D> DisjointSetWithData<D, F> {
+    /// `data[i]` is the value of the component consisting of only the node `i`
+    pub fn new(data: impl IntoIterator<Item=D>, merge: F) -> Self {
+        let data = data.into_iter().map(Some).collect::<Vec<_>>();
+        Self {
+            dsu: DisjointSet::new(data.len()),
+            data,
+            merge,
+        }
+    }
+    pub fn find(&mut self, x: usize) -> usize {
+        self.dsu.find(x)
+    }
+    pub fn join(&mut self, x: usize, y: usize) -> bool {
+        let (x, y) = (self.dsu.find(x), self.dsu.find(y));
+        if !self.dsu.join(x, y) {
+            return false;
+        }
+        let (root, child) = if self.dsu.find(x) == x { (x, y) } else { (y, x) };
+        let root_data = self.data[root].take().unwrap();
+        let child_data = self.data[child].take().unwrap();
+        self.data[root] = Some((self.merge)(root_data, child_data));
+        true
+    }
+    /// Returns the value of the component containing `x`
+    pub fn data(&mut self, x: usize) -> &D {
+        let root = self.dsu.find(x);
+        self.data[root].as_ref().unwrap()
+    }
+    pub fn data_mut(&mut self, x: usize) -> &mut D {
+        let root = self.dsu.find(x);
+        self.data[root].as_mut().unwrap()
+    }
+    pub fn add_node(&mut self, data: D) -> usize {
+        self.data.push(Some(data));
+        self.dsu.add_node()
+    }
+    pub fn same(&mut self, x: usize, y: usize) -> bool {
+        self.dsu.same(x, y)
+    }
+    pub fn size(&mut self, x: usize) -> usize {
+        self.dsu.size(x)
+    }
+    pub fn len(&self) -> usize {
+        self.dsu.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.dsu.is_empty()
+    }
+    pub fn count_components(&self) -> usize {
+        self.dsu.count_components()
+    }
+    pub fn groups(&mut self) -> Vec<Vec<usize>> {
+        self.dsu.groups()
+    }
+}
+
+/// Disjoint set with union by size only, so that joins can be undone
+pub struct RollbackDisjointSet {
+    data: Vec<usize>,
+    size: Vec<usize>,
+    // roots that were attached to another root, in order
+    history: Vec<usize>,
+}
+
+impl RollbackDisjointSet {
+    pub fn new(n: usize) -> Self {
+        Self {
+            data: (0..n).collect(),
+            size: vec![1; n],
+            history: vec![],
+        }
+    }
+    pub fn find(&self, mut x: usize) -> usize {
+        while self.data[x] != x {
+            x = self.data[x];
+        }
+        x
+    }
+    pub fn join(&mut self, x: usize, y: usize) -> bool {
+        let (mut x, mut y) = (self.find(x), self.find(y));
+
+        if x == y {
+            return false;
+        }
+
+        if self.size[x] < self.size[y] {
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        self.size[x] += self.size[y];
+        self.data[y] = x;
+        self.history.push(y);
+
+        true
+    }
+    pub fn same(&self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
+    pub fn size(&self, x: usize) -> usize {
+        self.size[self.find(x)]
+    }
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    /// Returns a state which can be later restored by `rollback`
+    pub fn snapshot(&self) -> usize {
+        self.history.len()
+    }
+    /// Undoes all the successful joins since the `snapshot` was taken
+    pub fn rollback(&mut self, snapshot: usize) {
+        while self.history.len() > snapshot {
+            let y = self.history.pop().unwrap();
+            let x = self.data[y];
+            self.size[x] -= self.size[y];
+            self.data[y] = y;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectivityQuery {
+    AddEdge(usize, usize),
+    RemoveEdge(usize, usize),
+    Connected(usize, usize),
+}
+
+/// Answers the `Connected` queries in order, each one against the graph formed by the edges added and not yet removed before it.
+/// Multiple edges between the same pair of nodes are allowed, `RemoveEdge` removes one of them.
+pub fn offline_dynamic_connectivity(n: usize, queries: &[ConnectivityQuery]) -> Vec<bool> {
+    let q = queries.len();
+    if q == 0 {
+        return vec![];
+    }
+
+    // edges alive during times [l, r) are stored in the O(log q) segment tree nodes covering it
+    let mut tree = vec![vec![]; q*4];
+    fn add_edge(tree: &mut Vec<Vec<(usize, usize)>>, l: usize, r: usize, edge: (usize, usize), ti: usize, tl: usize, tr: usize) {
+        if l >= r {
+            return;
+        }
+        if tl == l && tr == r {
+            tree[ti].push(edge);
+            return;
+        }
+        let tm = (tl+tr)/2;
+        add_edge(tree, l, r.min(tm), edge, ti*2, tl, tm);
+        add_edge(tree, l.max(tm), r, edge, ti*2+1, tm, tr);
+    }
+
+    let mut added = std::collections::HashMap::<(usize, usize), Vec<usize>>::new();
+    for (t, query) in queries.iter().enumerate() {
+        match *query {
+            ConnectivityQuery::AddEdge(u, v) => added.entry((u.min(v), u.max(v))).or_default().push(t),
+            ConnectivityQuery::RemoveEdge(u, v) => {
+                let edge = (u.min(v), u.max(v));
+                let start = added.get_mut(&edge).and_then(|starts| starts.pop()).expect("removed edge is not in the graph");
+                add_edge(&mut tree, start, t, edge, 1, 0, q);
+            }
+            ConnectivityQuery::Connected(_, _) => {}
+        }
+    }
+    for (edge, starts) in added {
+        for start in starts {
+            add_edge(&mut tree, start, q, edge, 1, 0, q);
+        }
+    }
+
+    fn solve(
+        tree: &Vec<Vec<(usize, usize)>>,
+        queries: &[ConnectivityQuery],
+        dsu: &mut RollbackDisjointSet,
+        result: &mut Vec<bool>,
+        ti: usize,
+        tl: usize,
+        tr: usize,
+    ) {
+        let snapshot = dsu.snapshot();
+        for &(u, v) in &tree[ti] {
+            dsu.join(u, v);
+        }
+        if tl+1 == tr {
+            if let ConnectivityQuery::Connected(u, v) = queries[tl] {
+                result.push(dsu.same(u, v));
+            }
+        } else {
+            let tm = (tl+tr)/2;
+            solve(tree, queries, dsu, result, ti*2, tl, tm);
+            solve(tree, queries, dsu, result, ti*2+1, tm, tr);
+        }
+        dsu.rollback(snapshot);
+    }
+
+    let mut dsu = RollbackDisjointSet::new(n);
+    let mut result = vec![];
+    solve(&tree, queries, &mut dsu, &mut result, 1, 0, q);
+    result
+}
+
+/// Disjoint set keeping the potential of every node relative to its root, for systems of constraints `x_u - x_v = w`
+/// over a commutative group, e.g. `i64` for offsets or `Xor` for parity
+pub struct WeightedDisjointSet<T: AdditiveGroup + PartialEq> {
+    data: Vec<usize>,
+    size: Vec<usize>,
+    // weight[x] = x_x - x_data[x]
+    weight: Vec<T>,
+}
+
+impl<T: AdditiveGroup + PartialEq> WeightedDisjointSet<T> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            data: (0..n).collect(),
+            size: vec![1; n],
+            weight: vec![T::zero(); n],
+        }
+    }
+    /// Returns the root of `x` and `x_x - x_root`
+    pub fn find(&mut self, x: usize) -> (usize, T) {
+        let mut path = vec![];
+        let mut root = x;
+        while self.data[root] != root {
+            path.push(root);
+            root = self.data[root];
+        }
+        // the node closest to the root is last, its weight is already relative to the root
+        for &y in path.iter().rev().skip(1) {
+            let parent = self.data[y];
+            self.weight[y] = self.weight[y] + self.weight[parent];
+        }
+        for &y in &path {
+            self.data[y] = root;
+        }
+        (root, if x == root { T::zero() } else { self.weight[x] })
+    }
+    /// Adds the constraint `x_x - x_y = w`.
+    /// Returns `Ok(true)` if two components were joined, `Ok(false)` if the constraint already followed from the previous ones
+    /// and `Err(d)` with the implied difference `d = x_x - x_y` if it contradicts them, in which case nothing changes.
+    pub fn join(&mut self, x: usize, y: usize, w: T) -> Result<bool, T> {
+        let (mut rx, px) = self.find(x);
+        let (mut ry, py) = self.find(y);
+
+        if rx == ry {
+            let d = px - py;
+            return if d == w { Ok(false) } else { Err(d) };
+        }
+
+        // x_rx - x_ry = w - px + py
+        let mut root_diff = w - px + py;
+        if self.size[rx] > self.size[ry] {
+            std::mem::swap(&mut rx, &mut ry);
+            root_diff = T::zero() - root_diff;
+        }
+
+        self.size[ry] += self.size[rx];
+        self.data[rx] = ry;
+        self.weight[rx] = root_diff;
+
+        Ok(true)
+    }
+    /// Returns `x_x - x_y` if `x` and `y` are in the same component
+    pub fn diff(&mut self, x: usize, y: usize) -> Option<T> {
+        let (rx, px) = self.find(x);
+        let (ry, py) = self.find(y);
+        if rx == ry { Some(px - py) } else { None }
+    }
+    pub fn same(&mut self, x: usize, y: usize) -> bool {
+        self.find(x).0 == self.find(y).0
+    }
+    pub fn size(&mut self, x: usize) -> usize {
+        let x = self.find(x).0;
+        self.size[x]
+    }
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{math::group::Xor, random::XorShift};
+
+    use super::*;
+
+    #[test]
+    fn test_rollback() {
+        let mut dsu = RollbackDisjointSet::new(5);
+        dsu.join(0, 1);
+        let snapshot = dsu.snapshot();
+        assert!(dsu.join(2, 3));
+        assert!(dsu.join(1, 3));
+        assert!(!dsu.join(0, 2));
+        assert_eq!(dsu.size(0), 4);
+        dsu.rollback(snapshot);
+        assert!(dsu.same(0, 1));
+        assert!(!dsu.same(1, 2));
+        assert!(!dsu.same(2, 3));
+        assert_eq!(dsu.size(0), 2);
+        assert_eq!(dsu.size(3), 1);
+    }
+
+    #[test]
+    fn test_dynamic_connectivity_brute() {
+        let mut rand = XorShift::new(13);
+        let n = 12;
+        let mut edges: Vec<(usize, usize)> = vec![];
+        let mut queries = vec![];
+        let mut expected = vec![];
+        for _ in 0..2000 {
+            let (u, v) = (rand.next() as usize % n, rand.next() as usize % n);
+            match rand.next() % 3 {
+                0 => {
+                    edges.push((u, v));
+                    queries.push(ConnectivityQuery::AddEdge(u, v));
+                }
+                1 if !edges.is_empty() => {
+                    let (u, v) = edges.swap_remove(rand.next() as usize % edges.len());
+                    queries.push(ConnectivityQuery::RemoveEdge(v, u));
+                }
+                _ => {
+                    let mut dsu = DisjointSet::new(n);
+                    for &(a, b) in &edges {
+                        dsu.join(a, b);
+                    }
+                    expected.push(dsu.find(u) == dsu.find(v));
+                    queries.push(ConnectivityQuery::Connected(u, v));
+                }
+            }
+        }
+        assert_eq!(offline_dynamic_connectivity(n, &queries), expected);
+    }
+
+    #[test]
+    fn test_weighted_offsets() {
+        let mut dsu = WeightedDisjointSet::new(5);
+        assert_eq!(dsu.join(0, 1, 3), Ok(true));
+        assert_eq!(dsu.join(1, 2, -5), Ok(true));
+        assert_eq!(dsu.diff(0, 2), Some(-2));
+        assert_eq!(dsu.diff(2, 0), Some(2));
+        assert_eq!(dsu.diff(0, 3), None);
+        assert_eq!(dsu.join(2, 0, 2), Ok(false));
+        assert_eq!(dsu.join(2, 0, 3), Err(2));
+        assert_eq!(dsu.join(3, 4, 10), Ok(true));
+        assert_eq!(dsu.join(4, 0, 1), Ok(true));
+        assert_eq!(dsu.diff(3, 2), Some(9));
+        assert_eq!(dsu.size(2), 5);
+    }
+
+    #[test]
+    fn test_weighted_brute() {
+        let mut rand = XorShift::new(14);
+        let n = 50;
+        let values = (0..n).map(|_| (rand.next() % 1000) as i64).collect::<Vec<_>>();
+        let mut dsu = WeightedDisjointSet::new(n);
+        let mut plain = DisjointSet::new(n);
+        for _ in 0..200 {
+            let (u, v) = (rand.next() as usize % n, rand.next() as usize % n);
+            let joined = plain.join(u, v);
+            assert_eq!(dsu.join(u, v, values[u] - values[v]), Ok(joined));
+            assert_eq!(dsu.join(u, v, values[u] - values[v] + 1), Err(values[u] - values[v]));
+            let (x, y) = (rand.next() as usize % n, rand.next() as usize % n);
+            let expected = (plain.find(x) == plain.find(y)).then(|| values[x] - values[y]);
+            assert_eq!(dsu.diff(x, y), expected);
+            assert_eq!(dsu.size(x), plain.size(x));
+        }
+    }
+
+    #[test]
+    fn test_parity() {
+        // a 4-cycle is bipartite, closing a triangle is not
+        let mut dsu = WeightedDisjointSet::new(4);
+        assert_eq!(dsu.join(0, 1, Xor(1)), Ok(true));
+        assert_eq!(dsu.join(1, 2, Xor(1)), Ok(true));
+        assert_eq!(dsu.join(2, 3, Xor(1)), Ok(true));
+        assert_eq!(dsu.join(3, 0, Xor(1)), Ok(false));
+        assert_eq!(dsu.diff(0, 2), Some(Xor(0)));
+        assert_eq!(dsu.join(0, 2, Xor(1)), Err(Xor(0)));
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut dsu = DisjointSet::new(6);
+        assert_eq!(dsu.count_components(), 6);
+        dsu.join(4, 1);
+        dsu.join(5, 3);
+        dsu.join(1, 5);
+        assert!(dsu.same(1, 3));
+        assert!(!dsu.same(0, 3));
+        assert_eq!(dsu.count_components(), 3);
+        assert_eq!(dsu.groups(), vec![vec![0], vec![1, 3, 4, 5], vec![2]]);
+
+        let x = dsu.add_node();
+        assert_eq!(x, 6);
+        assert_eq!(dsu.count_components(), 4);
+        dsu.join(x, 2);
+        assert_eq!(dsu.groups(), vec![vec![0], vec![1, 3, 4, 5], vec![2, 6]]);
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let n = 1_000_000;
+        let mut dsu = DisjointSet::new(n);
+        for i in 1..n {
+            dsu.data[i] = i-1;
+        }
+        assert_eq!(dsu.find_ref(n-1), 0);
+        assert_eq!(dsu.find(n-1), 0);
+        assert_eq!(dsu.data[n-1], 0);
+        assert_eq!(dsu.data[n/2], 0);
+    }
+
+    #[test]
+    fn test_with_data() {
+        // (min, sum) of each component
+        let mut dsu = DisjointSetWithData::new([5, 3, 8, 1].map(|x| (x, x)), |a: (i32, i32), b: (i32, i32)| (a.0.min(b.0), a.1+b.1));
+        assert!(dsu.join(0, 1));
+        assert!(!dsu.join(1, 0));
+        assert_eq!(*dsu.data(1), (3, 8));
+        dsu.join(2, 3);
+        assert_eq!(*dsu.data(2), (1, 9));
+        let x = dsu.add_node((0, 100));
+        dsu.join(x, 0);
+        assert_eq!(*dsu.data(1), (0, 108));
+        dsu.data_mut(3).1 = 0;
+        assert_eq!(*dsu.data(2), (1, 0));
+        assert_eq!(dsu.count_components(), 2);
+    }
 }
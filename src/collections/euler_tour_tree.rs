@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+
+use crate::{math::AdditiveGroup, random::rand_u64};
+
+const NIL: usize = usize::MAX;
+
+struct EulerTourNode<T> {
+    left: usize,
+    right: usize,
+    parent: usize,
+    priority: u64,
+    // number of nodes in the treap subtree
+    size: usize,
+    // number of vertex nodes in the treap subtree
+    vertices: usize,
+    // value of the vertex, zero for edge nodes
+    value: T,
+    sum: T,
+}
+
+/// Dynamic forest over vertices `0..n` supporting link, cut, connectivity and subtree or component sums in expected O(log n).
+/// Each tree is kept as its euler tour in a treap, with one node per vertex and one per directed edge.
+pub struct EulerTourTree<T: AdditiveGroup> {
+    // nodes 0..n are the vertices
+    n: usize,
+    nodes: Vec<EulerTourNode<T>>,
+    // (u, v) -> node of the directed edge u -> v
+    edges: HashMap<(usize, usize), usize>,
+    free: Vec<usize>,
+}
+
+impl<T: AdditiveGroup> EulerTourTree<T> {
+    pub fn new(n: usize) -> Self {
+        Self::new_from_iter((0..n).map(|_| T::zero()))
+    }
+
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>) -> Self {
+        let mut result = Self {
+            n: 0,
+            nodes: vec![],
+            edges: HashMap::new(),
+            free: vec![],
+        };
+        for value in iter {
+            result.new_node(value, 1);
+        }
+        result.n = result.nodes.len();
+        result
+    }
+
+    fn new_node(&mut self, value: T, vertices: usize) -> usize {
+        let node = EulerTourNode {
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+            priority: rand_u64(),
+            size: 1,
+            vertices,
+            value,
+            sum: value,
+        };
+        match self.free.pop() {
+            Some(x) => {
+                self.nodes[x] = node;
+                x
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len()-1
+            }
+        }
+    }
+
+    /// Number of vertices
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn size(&self, x: usize) -> usize {
+        if x == NIL { 0 } else { self.nodes[x].size }
+    }
+
+    fn update(&mut self, x: usize) {
+        let (l, r) = (self.nodes[x].left, self.nodes[x].right);
+        let mut size = 1;
+        let mut vertices = (x < self.n) as usize;
+        let mut sum = self.nodes[x].value;
+        for c in [l, r] {
+            if c != NIL {
+                size += self.nodes[c].size;
+                vertices += self.nodes[c].vertices;
+                sum = sum + self.nodes[c].sum;
+                self.nodes[c].parent = x;
+            }
+        }
+        let node = &mut self.nodes[x];
+        node.size = size;
+        node.vertices = vertices;
+        node.sum = sum;
+    }
+
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.nodes[a].priority > self.nodes[b].priority {
+            let right = self.nodes[a].right;
+            self.nodes[a].right = self.merge(right, b);
+            self.update(a);
+            self.nodes[a].parent = NIL;
+            a
+        }
+        else {
+            let left = self.nodes[b].left;
+            self.nodes[b].left = self.merge(a, left);
+            self.update(b);
+            self.nodes[b].parent = NIL;
+            b
+        }
+    }
+
+    /// Splits off the first `k` nodes of the treap rooted at `t`
+    fn split(&mut self, t: usize, k: usize) -> (usize, usize) {
+        if t == NIL {
+            return (NIL, NIL);
+        }
+        let left = self.nodes[t].left;
+        self.nodes[t].parent = NIL;
+        if self.size(left) >= k {
+            let (a, b) = self.split(left, k);
+            self.nodes[t].left = b;
+            self.update(t);
+            if a != NIL {
+                self.nodes[a].parent = NIL;
+            }
+            (a, t)
+        }
+        else {
+            let right = self.nodes[t].right;
+            let (a, b) = self.split(right, k - self.size(left) - 1);
+            self.nodes[t].right = a;
+            self.update(t);
+            if b != NIL {
+                self.nodes[b].parent = NIL;
+            }
+            (t, b)
+        }
+    }
+
+    fn root(&self, mut x: usize) -> usize {
+        while self.nodes[x].parent != NIL {
+            x = self.nodes[x].parent;
+        }
+        x
+    }
+
+    /// Position of node `x` in its tour
+    fn index(&self, mut x: usize) -> usize {
+        let mut result = self.size(self.nodes[x].left);
+        while self.nodes[x].parent != NIL {
+            let p = self.nodes[x].parent;
+            if self.nodes[p].right == x {
+                result += self.size(self.nodes[p].left) + 1;
+            }
+            x = p;
+        }
+        result
+    }
+
+    /// Rotates the tour of `v`'s tree to start at `v` and returns its treap root
+    fn reroot(&mut self, v: usize) -> usize {
+        let k = self.index(v);
+        let (a, b) = self.split(self.root(v), k);
+        self.merge(b, a)
+    }
+
+    pub fn connected(&self, u: usize, v: usize) -> bool {
+        self.root(u) == self.root(v)
+    }
+
+    /// Adds the edge `u - v`, returns false if they are already connected
+    pub fn link(&mut self, u: usize, v: usize) -> bool {
+        if self.connected(u, v) {
+            return false;
+        }
+        let tu = self.reroot(u);
+        let tv = self.reroot(v);
+        let uv = self.new_node(T::zero(), 0);
+        let vu = self.new_node(T::zero(), 0);
+        self.edges.insert((u, v), uv);
+        self.edges.insert((v, u), vu);
+        let left = self.merge(tu, uv);
+        let right = self.merge(tv, vu);
+        self.merge(left, right);
+        true
+    }
+
+    /// Removes the edge `u - v`, returns false if there is no such edge
+    pub fn cut(&mut self, u: usize, v: usize) -> bool {
+        let (Some(mut uv), Some(mut vu)) = (self.edges.remove(&(u, v)), self.edges.remove(&(v, u))) else {
+            return false;
+        };
+        if self.index(uv) > self.index(vu) {
+            std::mem::swap(&mut uv, &mut vu);
+        }
+        // the tour is A uv B vu C, B becomes its own tree and A C the other one
+        let (i, j) = (self.index(uv), self.index(vu));
+        let (ab, c) = self.split(self.root(uv), j+1);
+        let (a, b) = self.split(ab, i);
+        let (_, b) = self.split(b, 1);
+        self.split(b, j-i-1);
+        self.merge(a, c);
+        self.free.push(uv);
+        self.free.push(vu);
+        true
+    }
+
+    pub fn get(&self, v: usize) -> T {
+        self.nodes[v].value
+    }
+
+    pub fn set(&mut self, v: usize, value: T) {
+        self.nodes[v].value = value;
+        let mut x = v;
+        while x != NIL {
+            self.update(x);
+            x = self.nodes[x].parent;
+        }
+    }
+
+    /// Number of vertices in the tree containing `v`
+    pub fn component_size(&self, v: usize) -> usize {
+        self.nodes[self.root(v)].vertices
+    }
+
+    pub fn component_sum(&self, v: usize) -> T {
+        self.nodes[self.root(v)].sum
+    }
+
+    /// Calls `f` with the tour segment `uv ... vu` of the subtree of `v` when its parent is `p`, `None` if there is no edge
+    fn with_subtree<R>(&mut self, v: usize, p: usize, f: impl FnOnce(&EulerTourNode<T>) -> R) -> Option<R> {
+        let &pv = self.edges.get(&(p, v))?;
+        let vp = self.edges[&(v, p)];
+        self.reroot(p);
+        let (i, j) = (self.index(pv), self.index(vp));
+        let (ab, c) = self.split(self.root(pv), j+1);
+        let (a, b) = self.split(ab, i);
+        let result = f(&self.nodes[b]);
+        let ab = self.merge(a, b);
+        self.merge(ab, c);
+        Some(result)
+    }
+
+    /// Number of vertices in the subtree of `v` when the tree is rooted on `p`'s side of the edge `p - v`
+    pub fn subtree_size(&mut self, v: usize, p: usize) -> Option<usize> {
+        self.with_subtree(v, p, |node| node.vertices)
+    }
+
+    /// Sum of the values in the subtree of `v` when the tree is rooted on `p`'s side of the edge `p - v`
+    pub fn subtree_sum(&mut self, v: usize, p: usize) -> Option<T> {
+        self.with_subtree(v, p, |node| node.sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    fn component(adj: &[Vec<usize>], v: usize, blocked: usize) -> Vec<usize> {
+        let mut seen = vec![false; adj.len()];
+        seen[v] = true;
+        if blocked != usize::MAX {
+            seen[blocked] = true;
+        }
+        let mut stack = vec![v];
+        let mut result = vec![];
+        while let Some(x) = stack.pop() {
+            result.push(x);
+            for &y in &adj[x] {
+                if !seen[y] {
+                    seen[y] = true;
+                    stack.push(y);
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn brute() {
+        let mut rand = random::XorShift::new(20);
+        let n = 40;
+        let mut values = (0..n as i64).collect::<Vec<_>>();
+        let mut ett = EulerTourTree::new_from_iter(values.clone());
+        let mut adj = vec![vec![]; n];
+        for _ in 0..20000 {
+            let (u, v) = (rand.next() as usize % n, rand.next() as usize % n);
+            match rand.next() % 5 {
+                0 | 1 => {
+                    let linked = !component(&adj, u, usize::MAX).contains(&v);
+                    assert_eq!(ett.link(u, v), linked);
+                    if linked {
+                        adj[u].push(v);
+                        adj[v].push(u);
+                    }
+                }
+                2 => {
+                    let has_edge = adj[u].contains(&v);
+                    assert_eq!(ett.cut(u, v), has_edge);
+                    if has_edge {
+                        adj[u].retain(|&x| x != v);
+                        adj[v].retain(|&x| x != u);
+                    }
+                }
+                3 => {
+                    let comp = component(&adj, u, usize::MAX);
+                    assert_eq!(ett.connected(u, v), comp.contains(&v));
+                    assert_eq!(ett.component_size(u), comp.len());
+                    assert_eq!(ett.component_sum(u), comp.iter().map(|&x| values[x]).sum::<i64>());
+                    if adj[u].contains(&v) {
+                        let sub = component(&adj, v, u);
+                        assert_eq!(ett.subtree_size(v, u), Some(sub.len()));
+                        assert_eq!(ett.subtree_sum(v, u), Some(sub.iter().map(|&x| values[x]).sum::<i64>()));
+                    }
+                    else {
+                        assert_eq!(ett.subtree_size(v, u), None);
+                    }
+                }
+                _ => {
+                    let value = (rand.next() % 100) as i64;
+                    ett.set(u, value);
+                    values[u] = value;
+                    assert_eq!(ett.get(u), value);
+                }
+            }
+            assert_eq!(ett.len(), n);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{BuildHasher, Hasher},
+};
+
+use crate::random::rand_u64;
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Hasher mixing every written word with splitmix64, much faster than the default SipHash on integer keys
+pub struct FastHasher {
+    state: u64,
+}
+
+impl Hasher for FastHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.write_u64(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.state = splitmix64(self.state ^ i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// Builds `FastHasher`s with a seed from `random::rand_u64`, so fixed anti-hash inputs can't target it.
+/// Seeding `random` makes the iteration order reproducible.
+#[derive(Clone)]
+pub struct FastBuildHasher {
+    seed: u64,
+}
+
+impl Default for FastBuildHasher {
+    fn default() -> Self {
+        Self { seed: rand_u64() }
+    }
+}
+
+impl BuildHasher for FastBuildHasher {
+    type Hasher = FastHasher;
+
+    fn build_hasher(&self) -> FastHasher {
+        FastHasher { state: self.seed }
+    }
+}
+
+pub type FastHashMap<K, V> = HashMap<K, V, FastBuildHasher>;
+pub type FastHashSet<K> = HashSet<K, FastBuildHasher>;
+
+/// Open addressing hash map from `u64` keys with linear probing and backward shift deletion.
+/// Slots are picked by the high bits of the key times a random odd multiplier.
+pub struct U64HashMap<V> {
+    slots: Vec<Option<(u64, V)>>,
+    len: usize,
+    multiplier: u64,
+    // 64 - log2(slots.len())
+    shift: u32,
+}
+
+impl<V> U64HashMap<V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let slots = (capacity*2).next_power_of_two().max(8);
+        Self {
+            slots: (0..slots).map(|_| None).collect(),
+            len: 0,
+            multiplier: rand_u64() | 1,
+            shift: 64 - slots.trailing_zeros(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn home(&self, key: u64) -> usize {
+        (key.wrapping_mul(self.multiplier) >> self.shift) as usize
+    }
+
+    /// Slot holding `key`, or the empty slot where it would be inserted
+    fn find(&self, key: u64) -> usize {
+        let mut i = self.home(key);
+        while let Some((k, _)) = &self.slots[i] {
+            if *k == key {
+                break;
+            }
+            i = (i+1) & self.mask();
+        }
+        i
+    }
+
+    fn grow(&mut self) {
+        let new_slots = (0..self.slots.len()*2).map(|_| None).collect();
+        let old = std::mem::replace(&mut self.slots, new_slots);
+        self.shift -= 1;
+        for (key, value) in old.into_iter().flatten() {
+            let i = self.find(key);
+            self.slots[i] = Some((key, value));
+        }
+    }
+
+    /// Inserts or replaces the value of `key`, returning the old one
+    pub fn insert(&mut self, key: u64, value: V) -> Option<V> {
+        if (self.len+1)*2 > self.slots.len() {
+            self.grow();
+        }
+        let i = self.find(key);
+        let old = self.slots[i].replace((key, value)).map(|(_, v)| v);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn get(&self, key: u64) -> Option<&V> {
+        self.slots[self.find(key)].as_ref().map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: u64) -> Option<&mut V> {
+        let i = self.find(key);
+        self.slots[i].as_mut().map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: u64) -> bool {
+        self.slots[self.find(key)].is_some()
+    }
+
+    /// Returns the value of `key`, inserting `f()` first if it is missing
+    pub fn get_or_insert_with(&mut self, key: u64, f: impl FnOnce() -> V) -> &mut V {
+        if !self.contains_key(key) {
+            self.insert(key, f());
+        }
+        self.get_mut(key).unwrap()
+    }
+
+    pub fn remove(&mut self, key: u64) -> Option<V> {
+        let mut i = self.find(key);
+        let (_, value) = self.slots[i].take()?;
+        self.len -= 1;
+        // move back later entries of the cluster whose probe sequence passes over the hole
+        let mut j = i;
+        loop {
+            j = (j+1) & self.mask();
+            let Some((k, _)) = &self.slots[j] else {
+                break;
+            };
+            let home = self.home(*k);
+            // distance from home to j against distance from i to j, both cyclic
+            if (j.wrapping_sub(home) & self.mask()) >= (j.wrapping_sub(i) & self.mask()) {
+                self.slots[i] = self.slots[j].take();
+                i = j;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+    }
+
+    /// Iterates over `(key, value)` pairs in unspecified order
+    pub fn iter(&self) -> impl Iterator<Item=(u64, &V)> {
+        self.slots.iter().flatten().map(|(k, v)| (*k, v))
+    }
+}
+
+impl<V> Default for U64HashMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn fast_hash_map() {
+        let mut rand = random::XorShift::new(26);
+        let mut map = FastHashMap::default();
+        let mut set = FastHashSet::default();
+        let mut reference = BTreeMap::new();
+        for _ in 0..10000 {
+            let key = (rand.next() % 500, format!("{}", rand.next() % 3));
+            let value = rand.next();
+            assert_eq!(map.insert(key.clone(), value), reference.insert(key.clone(), value));
+            set.insert(key);
+        }
+        assert_eq!(map.len(), reference.len());
+        assert_eq!(set.len(), reference.len());
+        for (key, value) in &reference {
+            assert_eq!(map.get(key), Some(value));
+        }
+    }
+
+    #[test]
+    fn seeded_hasher() {
+        let hash = |x: u64| FastBuildHasher::default().hash_one(x);
+        let a = random::with_seed(1, || hash(42));
+        let b = random::with_seed(1, || hash(42));
+        let c = random::with_seed(2, || hash(42));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn u64_hash_map_brute() {
+        let mut rand = random::XorShift::new(27);
+        let mut map = U64HashMap::new();
+        let mut reference = BTreeMap::new();
+        for _ in 0..50000 {
+            // small key range with some huge keys to get long clusters and many removals
+            let key = if rand.next().is_multiple_of(10) { rand.next() } else { rand.next() % 300 };
+            match rand.next() % 4 {
+                0 | 1 => {
+                    let value = rand.next();
+                    assert_eq!(map.insert(key, value), reference.insert(key, value));
+                }
+                2 => {
+                    assert_eq!(map.remove(key), reference.remove(&key));
+                }
+                _ => {
+                    assert_eq!(map.get(key), reference.get(&key));
+                    assert_eq!(map.contains_key(key), reference.contains_key(&key));
+                }
+            }
+            assert_eq!(map.len(), reference.len());
+        }
+        let mut items = map.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>();
+        items.sort();
+        assert_eq!(items, reference.into_iter().collect::<Vec<_>>());
+
+        *map.get_or_insert_with(7, || 0) += 5;
+        *map.get_or_insert_with(7, || 0) += 5;
+        assert_eq!(map.get(7), Some(&10));
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(7), None);
+    }
+}
@@ -0,0 +1,306 @@
+use std::ops::RangeBounds;
+
+use crate::math::AdditiveGroup;
+
+use super::to_range;
+
+/// Point update and range sum query
+pub struct FenwickTree<T: AdditiveGroup> {
+    // 1-indexed, data[0] is unused
+    data: Vec<T>,
+}
+
+impl<T: AdditiveGroup> FenwickTree<T> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            data: vec![T::zero(); n+1],
+        }
+    }
+
+    /// Builds the tree in O(n)
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>) -> Self {
+        let mut data = vec![T::zero()];
+        data.extend(iter);
+        for i in 1..data.len() {
+            let j = i + (i & i.wrapping_neg());
+            if j < data.len() {
+                data[j] = data[j] + data[i];
+            }
+        }
+        Self { data }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()-1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds `val` to the element at index `i`
+    pub fn add(&mut self, i: usize, val: T) {
+        let mut i = i+1;
+        while i < self.data.len() {
+            self.data[i] = self.data[i] + val;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Sum of the elements in `[0, r)`
+    pub fn prefix(&self, r: usize) -> T {
+        let mut i = r;
+        let mut result = T::zero();
+        while i > 0 {
+            result = result + self.data[i];
+            i -= i & i.wrapping_neg();
+        }
+        result
+    }
+
+    pub fn query(&self, range_bounds: impl RangeBounds<usize>) -> T {
+        let range = to_range(range_bounds, self.len());
+        self.prefix(range.end) - self.prefix(range.start)
+    }
+}
+
+impl<T: AdditiveGroup + PartialOrd> FenwickTree<T> {
+    /// Smallest `i` such that `query(..=i) >= val`, or `len()` if there is none.
+    /// All the elements have to be non-negative.
+    pub fn lower_bound(&self, val: T) -> usize {
+        let mut pos = 0;
+        let mut sum = T::zero();
+        let mut step = if self.is_empty() { 0 } else { 1 << self.len().ilog2() };
+        while step > 0 {
+            if pos + step <= self.len() && sum + self.data[pos+step] < val {
+                pos += step;
+                sum = sum + self.data[pos];
+            }
+            step /= 2;
+        }
+        pos
+    }
+}
+
+/// Range update and range sum query
+pub struct RangeFenwickTree<T: AdditiveGroup> {
+    b1: FenwickTree<T>,
+    b2: FenwickTree<T>,
+}
+
+impl<T: AdditiveGroup> RangeFenwickTree<T> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            b1: FenwickTree::new(n+1),
+            b2: FenwickTree::new(n+1),
+        }
+    }
+
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>) -> Self {
+        let init_data = iter.into_iter().collect::<Vec<_>>();
+        let mut result = Self::new(init_data.len());
+        for (i, val) in init_data.into_iter().enumerate() {
+            result.add(i..=i, val);
+        }
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.b1.len()-1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds `val` to every element in the range
+    pub fn add(&mut self, range_bounds: impl RangeBounds<usize>, val: T) {
+        let range = to_range(range_bounds, self.len());
+        if range.start >= range.end {
+            return;
+        }
+        self.b1.add(range.start, val);
+        self.b1.add(range.end, T::zero() - val);
+        self.b2.add(range.start, val.mul_usize(range.start));
+        self.b2.add(range.end, T::zero() - val.mul_usize(range.end));
+    }
+
+    /// Sum of the elements in `[0, r)`
+    pub fn prefix(&self, r: usize) -> T {
+        self.b1.prefix(r).mul_usize(r) - self.b2.prefix(r)
+    }
+
+    pub fn query(&self, range_bounds: impl RangeBounds<usize>) -> T {
+        let range = to_range(range_bounds, self.len());
+        self.prefix(range.end) - self.prefix(range.start)
+    }
+}
+
+/// Point update and rectangle sum query
+pub struct FenwickTree2D<T: AdditiveGroup> {
+    n: usize,
+    m: usize,
+    data: Vec<Vec<T>>,
+}
+
+impl<T: AdditiveGroup> FenwickTree2D<T> {
+    pub fn new(n: usize, m: usize) -> Self {
+        Self {
+            n,
+            m,
+            data: vec![vec![T::zero(); m+1]; n+1],
+        }
+    }
+
+    /// Adds `val` to the element at `(i, j)`
+    pub fn add(&mut self, i: usize, j: usize, val: T) {
+        let mut i = i+1;
+        while i <= self.n {
+            let mut j = j+1;
+            while j <= self.m {
+                self.data[i][j] = self.data[i][j] + val;
+                j += j & j.wrapping_neg();
+            }
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Sum of the elements in `[0, r) x [0, c)`
+    pub fn prefix(&self, r: usize, c: usize) -> T {
+        let mut result = T::zero();
+        let mut i = r;
+        while i > 0 {
+            let mut j = c;
+            while j > 0 {
+                result = result + self.data[i][j];
+                j -= j & j.wrapping_neg();
+            }
+            i -= i & i.wrapping_neg();
+        }
+        result
+    }
+
+    pub fn query(&self, rows: impl RangeBounds<usize>, cols: impl RangeBounds<usize>) -> T {
+        let rows = to_range(rows, self.n);
+        let cols = to_range(cols, self.m);
+        self.prefix(rows.end, cols.end) - self.prefix(rows.start, cols.end) - self.prefix(rows.end, cols.start)
+            + self.prefix(rows.start, cols.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::Wrapping;
+
+    use crate::random::XorShift;
+
+    use super::*;
+
+    #[test]
+    fn query_brute() {
+        let mut rand = XorShift::new(5);
+        let n = 200;
+        let mut data = (0..n).map(|_| (rand.next()%1000) as i64 - 500).collect::<Vec<_>>();
+        let mut ft = FenwickTree::new_from_iter(data.clone());
+        for _ in 0..1000 {
+            let i = rand.next() as usize % n;
+            let val = (rand.next()%1000) as i64 - 500;
+            ft.add(i, val);
+            data[i] += val;
+            let mut l = rand.next() as usize % n;
+            let mut r = rand.next() as usize % n;
+            if l > r {
+                std::mem::swap(&mut l, &mut r);
+            }
+            assert_eq!(ft.query(l..=r), data[l..=r].iter().sum::<i64>());
+            assert_eq!(ft.query(..), data.iter().sum::<i64>());
+        }
+    }
+
+    #[test]
+    fn lower_bound() {
+        let ft = FenwickTree::new_from_iter(vec![1i64, 0, 2, 3, 0, 1]);
+        assert_eq!(ft.lower_bound(0), 0);
+        assert_eq!(ft.lower_bound(1), 0);
+        assert_eq!(ft.lower_bound(2), 2);
+        assert_eq!(ft.lower_bound(3), 2);
+        assert_eq!(ft.lower_bound(4), 3);
+        assert_eq!(ft.lower_bound(6), 3);
+        assert_eq!(ft.lower_bound(7), 5);
+        assert_eq!(ft.lower_bound(8), 6);
+    }
+
+    #[test]
+    fn range_update_brute() {
+        let mut rand = XorShift::new(6);
+        let n = 200;
+        let mut data = (0..n).map(|_| (rand.next()%1000) as i64 - 500).collect::<Vec<_>>();
+        let mut ft = RangeFenwickTree::new_from_iter(data.clone());
+        for _ in 0..1000 {
+            let mut l = rand.next() as usize % n;
+            let mut r = rand.next() as usize % n;
+            if l > r {
+                std::mem::swap(&mut l, &mut r);
+            }
+            if rand.next().is_multiple_of(2) {
+                let val = (rand.next()%1000) as i64 - 500;
+                ft.add(l..=r, val);
+                for x in &mut data[l..=r] {
+                    *x += val;
+                }
+            }
+            else {
+                assert_eq!(ft.query(l..=r), data[l..=r].iter().sum::<i64>());
+            }
+        }
+    }
+
+    #[test]
+    fn range_update_wrapping() {
+        let mut rand = XorShift::new(8);
+        let n = 50;
+        let mut data = vec![0u64; n];
+        let mut ft = RangeFenwickTree::<Wrapping<u64>>::new(n);
+        for _ in 0..500 {
+            let mut l = rand.next() as usize % n;
+            let mut r = rand.next() as usize % n;
+            if l > r {
+                std::mem::swap(&mut l, &mut r);
+            }
+            // large values so the sums wrap around
+            let val = rand.next();
+            ft.add(l..=r, Wrapping(val));
+            for x in &mut data[l..=r] {
+                *x = x.wrapping_add(val);
+            }
+            let (l, r) = (rand.next() as usize % (n+1), rand.next() as usize % (n+1));
+            let expected = data[l.min(r)..l.max(r)].iter().fold(0u64, |a, b| a.wrapping_add(*b));
+            assert_eq!(ft.query(l.min(r)..l.max(r)), Wrapping(expected));
+        }
+    }
+
+    #[test]
+    fn query_2d_brute() {
+        let mut rand = XorShift::new(7);
+        let (n, m) = (20, 30);
+        let mut data = vec![vec![0i64; m]; n];
+        let mut ft = FenwickTree2D::new(n, m);
+        for _ in 0..1000 {
+            let (i, j) = (rand.next() as usize % n, rand.next() as usize % m);
+            let val = (rand.next()%1000) as i64 - 500;
+            ft.add(i, j, val);
+            data[i][j] += val;
+
+            let (mut r1, mut r2) = (rand.next() as usize % n, rand.next() as usize % n);
+            let (mut c1, mut c2) = (rand.next() as usize % m, rand.next() as usize % m);
+            if r1 > r2 {
+                std::mem::swap(&mut r1, &mut r2);
+            }
+            if c1 > c2 {
+                std::mem::swap(&mut c1, &mut c2);
+            }
+            let tar = data[r1..=r2].iter().map(|row| row[c1..=c2].iter().sum::<i64>()).sum::<i64>();
+            assert_eq!(ft.query(r1..=r2, c1..=c2), tar);
+        }
+    }
+}
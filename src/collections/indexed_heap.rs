@@ -0,0 +1,213 @@
+const ABSENT: usize = usize::MAX;
+
+/// Binary min-heap over ids `0..n`, each with a key that can be decreased or removed in O(log n)
+pub struct IndexedMinHeap<K: Ord> {
+    // heap[p] = id at heap position p
+    heap: Vec<usize>,
+    // pos[id] = position of id in heap, ABSENT if not contained
+    pos: Vec<usize>,
+    keys: Vec<Option<K>>,
+}
+
+impl<K: Ord> IndexedMinHeap<K> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            heap: vec![],
+            pos: vec![ABSENT; n],
+            keys: (0..n).map(|_| None).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Number of ids, not of contained ones
+    pub fn capacity(&self) -> usize {
+        self.pos.len()
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.pos[id] != ABSENT
+    }
+
+    pub fn key(&self, id: usize) -> Option<&K> {
+        self.keys[id].as_ref()
+    }
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        self.keys[self.heap[a]] < self.keys[self.heap[b]]
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.pos[self.heap[a]] = a;
+        self.pos[self.heap[b]] = b;
+    }
+
+    fn sift_up(&mut self, mut p: usize) {
+        while p > 0 && self.less(p, (p-1)/2) {
+            self.swap(p, (p-1)/2);
+            p = (p-1)/2;
+        }
+    }
+
+    fn sift_down(&mut self, mut p: usize) {
+        loop {
+            let mut smallest = p;
+            for child in [p*2+1, p*2+2] {
+                if child < self.heap.len() && self.less(child, smallest) {
+                    smallest = child;
+                }
+            }
+            if smallest == p {
+                return;
+            }
+            self.swap(p, smallest);
+            p = smallest;
+        }
+    }
+
+    /// Inserts `id` with `key`, or changes its key if already contained
+    pub fn push(&mut self, id: usize, key: K) {
+        if self.contains(id) {
+            self.change_key(id, key);
+            return;
+        }
+        self.keys[id] = Some(key);
+        self.pos[id] = self.heap.len();
+        self.heap.push(id);
+        self.sift_up(self.heap.len()-1);
+    }
+
+    pub fn peek(&self) -> Option<(usize, &K)> {
+        let &id = self.heap.first()?;
+        Some((id, self.keys[id].as_ref().unwrap()))
+    }
+
+    pub fn pop(&mut self) -> Option<(usize, K)> {
+        let &id = self.heap.first()?;
+        Some((id, self.remove(id).unwrap()))
+    }
+
+    /// Lowers the key of a contained `id`, panics if `key` is greater than the current one
+    pub fn decrease_key(&mut self, id: usize, key: K) {
+        assert!(self.contains(id), "id is not in the heap");
+        assert!(Some(&key) <= self.keys[id].as_ref(), "key is greater than the current one");
+        self.keys[id] = Some(key);
+        self.sift_up(self.pos[id]);
+    }
+
+    /// Sets the key of a contained `id` to any value
+    pub fn change_key(&mut self, id: usize, key: K) {
+        assert!(self.contains(id), "id is not in the heap");
+        self.keys[id] = Some(key);
+        self.sift_up(self.pos[id]);
+        self.sift_down(self.pos[id]);
+    }
+
+    /// Removes `id` and returns its key, `None` if it is not contained
+    pub fn remove(&mut self, id: usize) -> Option<K> {
+        if !self.contains(id) {
+            return None;
+        }
+        let p = self.pos[id];
+        let last = self.heap.len()-1;
+        self.swap(p, last);
+        self.heap.pop();
+        self.pos[id] = ABSENT;
+        if p < self.heap.len() {
+            let moved = self.heap[p];
+            self.sift_up(p);
+            self.sift_down(self.pos[moved]);
+        }
+        self.keys[id].take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    #[test]
+    fn brute() {
+        let mut rand = random::XorShift::new(13);
+        let n = 50;
+        let mut heap = IndexedMinHeap::new(n);
+        let mut reference: Vec<Option<u64>> = vec![None; n];
+        for _ in 0..10000 {
+            let id = rand.next() as usize % n;
+            match rand.next() % 5 {
+                0 | 1 => {
+                    let key = rand.next() % 1000;
+                    heap.push(id, key);
+                    reference[id] = Some(key);
+                }
+                2 => {
+                    if let Some(key) = reference[id] {
+                        let key = key - rand.next() % (key+1);
+                        heap.decrease_key(id, key);
+                        reference[id] = Some(key);
+                    }
+                }
+                3 => {
+                    assert_eq!(heap.remove(id), reference[id].take());
+                }
+                _ => {
+                    let expected = (0..n).filter_map(|i| reference[i].map(|k| (k, i))).min();
+                    let popped = heap.pop();
+                    assert_eq!(popped.as_ref().map(|(_, k)| *k), expected.map(|(k, _)| k));
+                    if let Some((i, k)) = popped {
+                        assert_eq!(reference[i], Some(k));
+                        reference[i] = None;
+                    }
+                }
+            }
+            assert_eq!(heap.len(), reference.iter().flatten().count());
+            assert_eq!(heap.key(id), reference[id].as_ref());
+        }
+    }
+
+    #[test]
+    fn dijkstra() {
+        let mut rand = random::XorShift::new(14);
+        let n = 200;
+        let mut adj = vec![vec![]; n];
+        for _ in 0..1000 {
+            let (u, v, w) = (rand.next() as usize % n, rand.next() as usize % n, rand.next() % 100);
+            adj[u].push((v, w));
+        }
+
+        let mut dist = vec![u64::MAX; n];
+        let mut heap = IndexedMinHeap::new(n);
+        dist[0] = 0;
+        heap.push(0, 0);
+        while let Some((u, d)) = heap.pop() {
+            for &(v, w) in &adj[u] {
+                if d + w < dist[v] {
+                    dist[v] = d + w;
+                    heap.push(v, d + w);
+                }
+            }
+        }
+
+        let mut expected = vec![u64::MAX; n];
+        expected[0] = 0;
+        for _ in 0..n {
+            for u in 0..n {
+                if expected[u] == u64::MAX {
+                    continue;
+                }
+                for &(v, w) in &adj[u] {
+                    expected[v] = expected[v].min(expected[u] + w);
+                }
+            }
+        }
+        assert_eq!(dist, expected);
+    }
+}
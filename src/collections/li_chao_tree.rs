@@ -0,0 +1,352 @@
+use std::ops::{Bound, RangeBounds};
+
+use crate::math::Integer;
+
+/// Line `y = a*x + b`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Line<T: Integer> {
+    pub a: T,
+    pub b: T,
+}
+
+impl<T: Integer> Line<T> {
+    pub fn new(a: T, b: T) -> Self {
+        Self { a, b }
+    }
+    /// Evaluates the line at `x`, in `i128` so that `a*x` can't overflow
+    pub fn eval(&self, x: T) -> i128 {
+        self.a.to_i64() as i128 * x.to_i64() as i128 + self.b.to_i64() as i128
+    }
+    fn eval_i64(&self, x: i64) -> i128 {
+        self.a.to_i64() as i128 * x as i128 + self.b.to_i64() as i128
+    }
+    /// Converts a result of `eval` back to `T`, panics if it doesn't fit
+    pub(crate) fn to_value(v: i128) -> T {
+        i64::try_from(v).ok()
+            .map(T::from_i64)
+            .filter(|t| t.to_i64() as i128 == v)
+            .expect("line value doesn't fit in the coordinate type")
+    }
+}
+
+/// Li Chao tree over a fixed set of query coordinates, answering minimum of the inserted lines at a point.
+/// For maximum insert negated lines and negate the result.
+pub struct LiChaoTree<T: Integer> {
+    xs: Vec<T>,
+    lines: Vec<Option<Line<T>>>,
+}
+
+impl<T: Integer> LiChaoTree<T> {
+    /// `xs` are all the coordinates that will ever be queried, they don't have to be sorted or unique
+    pub fn new(xs: impl IntoIterator<Item=T>) -> Self {
+        let mut xs = xs.into_iter().collect::<Vec<_>>();
+        xs.sort();
+        xs.dedup();
+        Self {
+            lines: vec![None; xs.len()*4],
+            xs,
+        }
+    }
+
+    fn add_inner(&mut self, mut line: Line<T>, ti: usize, tl: usize, tr: usize) {
+        let Some(mut cur) = self.lines[ti] else {
+            self.lines[ti] = Some(line);
+            return;
+        };
+        let tm = (tl+tr)/2;
+        let left_better = line.eval(self.xs[tl]) < cur.eval(self.xs[tl]);
+        let mid_better = line.eval(self.xs[tm]) < cur.eval(self.xs[tm]);
+        if mid_better {
+            std::mem::swap(&mut line, &mut cur);
+        }
+        self.lines[ti] = Some(cur);
+        if tl+1 == tr {
+            return;
+        }
+        if left_better != mid_better {
+            self.add_inner(line, ti*2, tl, tm);
+        }
+        else {
+            self.add_inner(line, ti*2+1, tm, tr);
+        }
+    }
+
+    fn add_segment_inner(&mut self, line: Line<T>, l: usize, r: usize, ti: usize, tl: usize, tr: usize) {
+        if l >= r {
+            return;
+        }
+        if tl == l && tr == r {
+            self.add_inner(line, ti, tl, tr);
+            return;
+        }
+        let tm = (tl+tr)/2;
+        self.add_segment_inner(line, l, r.min(tm), ti*2, tl, tm);
+        self.add_segment_inner(line, l.max(tm), r, ti*2+1, tm, tr);
+    }
+
+    pub fn add_line(&mut self, line: Line<T>) {
+        if self.xs.is_empty() {
+            return;
+        }
+        self.add_inner(line, 1, 0, self.xs.len());
+    }
+
+    /// Inserts the line only for coordinates within `range`
+    pub fn add_segment(&mut self, line: Line<T>, range: impl RangeBounds<T>) {
+        let l = match range.start_bound() {
+            Bound::Included(x) => self.xs.partition_point(|v| v < x),
+            Bound::Excluded(x) => self.xs.partition_point(|v| v <= x),
+            Bound::Unbounded => 0,
+        };
+        let r = match range.end_bound() {
+            Bound::Included(x) => self.xs.partition_point(|v| v <= x),
+            Bound::Excluded(x) => self.xs.partition_point(|v| v < x),
+            Bound::Unbounded => self.xs.len(),
+        };
+        self.add_segment_inner(line, l, r, 1, 0, self.xs.len());
+    }
+
+    /// Minimum over all lines at `x`, `x` must be one of the coordinates given in `new`
+    pub fn query(&self, x: T) -> Option<T> {
+        let i = self.xs.binary_search(&x).expect("queried coordinate was not given to LiChaoTree::new");
+        let (mut ti, mut tl, mut tr) = (1, 0, self.xs.len());
+        let mut result: Option<i128> = None;
+        loop {
+            if let Some(line) = self.lines[ti] {
+                let val = line.eval(x);
+                if result.is_none_or(|r| val < r) {
+                    result = Some(val);
+                }
+            }
+            if tl+1 == tr {
+                break;
+            }
+            let tm = (tl+tr)/2;
+            if i < tm {
+                (ti, tr) = (ti*2, tm);
+            }
+            else {
+                (ti, tl) = (ti*2+1, tm);
+            }
+        }
+        result.map(Line::to_value)
+    }
+}
+
+struct DynamicNode<T: Integer> {
+    line: Option<Line<T>>,
+    left: usize,
+    right: usize,
+}
+
+/// Li Chao tree over the integer coordinate range `[lo, hi)` which creates nodes only when needed
+pub struct DynamicLiChaoTree<T: Integer> {
+    lo: i64,
+    hi: i64,
+    // index 0 is the root, so 0 is used as a missing child
+    nodes: Vec<DynamicNode<T>>,
+}
+
+impl<T: Integer> DynamicLiChaoTree<T> {
+    pub fn new(lo: T, hi: T) -> Self {
+        assert!(lo < hi);
+        Self {
+            lo: lo.to_i64(),
+            hi: hi.to_i64(),
+            nodes: vec![DynamicNode { line: None, left: 0, right: 0 }],
+        }
+    }
+
+    fn mid(tl: i64, tr: i64) -> i64 {
+        tl + (tr-tl)/2
+    }
+
+    fn child(&mut self, ti: usize, is_left: bool) -> usize {
+        let child = if is_left { self.nodes[ti].left } else { self.nodes[ti].right };
+        if child != 0 {
+            return child;
+        }
+        self.nodes.push(DynamicNode { line: None, left: 0, right: 0 });
+        let child = self.nodes.len()-1;
+        if is_left {
+            self.nodes[ti].left = child;
+        }
+        else {
+            self.nodes[ti].right = child;
+        }
+        child
+    }
+
+    fn add_inner(&mut self, mut line: Line<T>, ti: usize, tl: i64, tr: i64) {
+        let Some(mut cur) = self.nodes[ti].line else {
+            self.nodes[ti].line = Some(line);
+            return;
+        };
+        let tm = Self::mid(tl, tr);
+        let left_better = line.eval_i64(tl) < cur.eval_i64(tl);
+        let mid_better = line.eval_i64(tm) < cur.eval_i64(tm);
+        if mid_better {
+            std::mem::swap(&mut line, &mut cur);
+        }
+        self.nodes[ti].line = Some(cur);
+        if tl+1 == tr {
+            return;
+        }
+        if left_better != mid_better {
+            let child = self.child(ti, true);
+            self.add_inner(line, child, tl, tm);
+        }
+        else {
+            let child = self.child(ti, false);
+            self.add_inner(line, child, tm, tr);
+        }
+    }
+
+    fn add_segment_inner(&mut self, line: Line<T>, l: i64, r: i64, ti: usize, tl: i64, tr: i64) {
+        if l >= r {
+            return;
+        }
+        if tl == l && tr == r {
+            self.add_inner(line, ti, tl, tr);
+            return;
+        }
+        let tm = Self::mid(tl, tr);
+        if l < tm {
+            let child = self.child(ti, true);
+            self.add_segment_inner(line, l, r.min(tm), child, tl, tm);
+        }
+        if r > tm {
+            let child = self.child(ti, false);
+            self.add_segment_inner(line, l.max(tm), r, child, tm, tr);
+        }
+    }
+
+    pub fn add_line(&mut self, line: Line<T>) {
+        self.add_inner(line, 0, self.lo, self.hi);
+    }
+
+    /// Inserts the line only for coordinates within `range`
+    pub fn add_segment(&mut self, line: Line<T>, range: impl RangeBounds<T>) {
+        let l = match range.start_bound() {
+            Bound::Included(x) => x.to_i64(),
+            Bound::Excluded(x) => x.to_i64()+1,
+            Bound::Unbounded => self.lo,
+        };
+        let r = match range.end_bound() {
+            Bound::Included(x) => x.to_i64()+1,
+            Bound::Excluded(x) => x.to_i64(),
+            Bound::Unbounded => self.hi,
+        };
+        self.add_segment_inner(line, l.max(self.lo), r.min(self.hi), 0, self.lo, self.hi);
+    }
+
+    /// Minimum over all lines at `x`, which has to be in `[lo, hi)`
+    pub fn query(&self, x: T) -> Option<T> {
+        let x = x.to_i64();
+        assert!(self.lo <= x && x < self.hi);
+        let (mut ti, mut tl, mut tr) = (0, self.lo, self.hi);
+        let mut result: Option<i128> = None;
+        loop {
+            if let Some(line) = self.nodes[ti].line {
+                let val = line.eval_i64(x);
+                if result.is_none_or(|r| val < r) {
+                    result = Some(val);
+                }
+            }
+            let tm = Self::mid(tl, tr);
+            let child = if x < tm {
+                tr = tm;
+                self.nodes[ti].left
+            }
+            else {
+                tl = tm;
+                self.nodes[ti].right
+            };
+            if child == 0 {
+                break;
+            }
+            ti = child;
+        }
+        result.map(Line::to_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::XorShift;
+
+    use super::*;
+
+    fn brute(lines: &[(Line<i64>, i64, i64)], x: i64) -> Option<i64> {
+        lines.iter().filter(|(_, l, r)| *l <= x && x < *r).map(|(line, _, _)| line.eval(x) as i64).min()
+    }
+
+    #[test]
+    fn lines_brute() {
+        let mut rand = XorShift::new(1);
+        let xs = (0..200).map(|_| (rand.next()%2001) as i64 - 1000).collect::<Vec<_>>();
+        let mut lct = LiChaoTree::new(xs.clone());
+        let mut dlct = DynamicLiChaoTree::new(-1000i64, 1001);
+        let mut lines = vec![];
+        for _ in 0..300 {
+            let line = Line::new((rand.next()%2001) as i64 - 1000, (rand.next()%2_000_001) as i64 - 1_000_000);
+            lct.add_line(line);
+            dlct.add_line(line);
+            lines.push((line, i64::MIN, i64::MAX));
+            for &x in &xs {
+                assert_eq!(lct.query(x), brute(&lines, x));
+                assert_eq!(dlct.query(x), brute(&lines, x));
+            }
+        }
+    }
+
+    #[test]
+    fn segments_brute() {
+        let mut rand = XorShift::new(2);
+        let xs = (-100..100).collect::<Vec<i64>>();
+        let mut lct = LiChaoTree::new(xs.clone());
+        let mut dlct = DynamicLiChaoTree::new(-100i64, 100);
+        let mut lines = vec![];
+        for _ in 0..300 {
+            let line = Line::new((rand.next()%201) as i64 - 100, (rand.next()%20_001) as i64 - 10_000);
+            let mut l = (rand.next()%200) as i64 - 100;
+            let mut r = (rand.next()%200) as i64 - 100;
+            if l > r {
+                std::mem::swap(&mut l, &mut r);
+            }
+            lct.add_segment(line, l..=r);
+            dlct.add_segment(line, l..=r);
+            lines.push((line, l, r+1));
+            for &x in &xs {
+                assert_eq!(lct.query(x), brute(&lines, x));
+                assert_eq!(dlct.query(x), brute(&lines, x));
+            }
+        }
+    }
+
+    #[test]
+    fn large_values() {
+        let mut dlct = DynamicLiChaoTree::new(-1_000_000_000i64, 1_000_000_001);
+        dlct.add_line(Line::new(1_000_000_000, -1_000_000_000_000_000_000));
+        dlct.add_line(Line::new(-1_000_000_000, 0));
+        assert_eq!(dlct.query(1_000_000_000), Some(-1_000_000_000_000_000_000));
+        assert_eq!(dlct.query(0), Some(-1_000_000_000_000_000_000));
+        assert_eq!(dlct.query(-1_000_000_000), Some(-2_000_000_000_000_000_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn overflow_i64() {
+        let mut lct = LiChaoTree::new([i64::MAX]);
+        lct.add_line(Line::new(2, 0));
+        lct.query(i64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn overflow_i32() {
+        let mut dlct = DynamicLiChaoTree::new(0i32, 100_000);
+        dlct.add_line(Line::new(100_000, 0));
+        dlct.query(99_999);
+    }
+}
@@ -0,0 +1,398 @@
+const NIL: usize = usize::MAX;
+
+struct LinkCutNode<D, L> {
+    // children in the splay tree of the preferred path, ordered from the root of the represented tree downwards
+    ch: [usize; 2],
+    // splay parent, or path parent if this is the root of its splay tree
+    parent: usize,
+    val: D,
+    agg: D,
+    // aggregate of the splay subtree in reversed order, needed for `make_root` with non-commutative `m`
+    rev_agg: D,
+    lazy: Option<L>,
+    // the children of this node still have to be reversed, its own children are already swapped
+    rev: bool,
+}
+
+/// Dynamic forest over vertices `0..n` with link, cut, re-root, path aggregates and lazy path updates in amortized O(log n).
+/// `m` has to be associative, `ml(old, new)` composes updates and `u` applies an update to an aggregate.
+pub struct LinkCutTree<D: Clone, L: Clone, M: Fn(D, D) -> D, ML: Fn(L, L) -> L, U: Fn(D, L) -> D> {
+    nodes: Vec<LinkCutNode<D, L>>,
+    m: M,
+    ml: ML,
+    u: U
+}
+
+impl<D: Clone, L: Clone, M: Fn(D, D) -> D, ML: Fn(L, L) -> L, U: Fn(D, L) -> D> LinkCutTree<D, L, M, ML, U> {
+    /// Creates a forest of isolated vertices with the given values
+    pub fn from_iter<I: IntoIterator<Item=D>>(iter: I, m: M, ml: ML, u: U) -> Self {
+        let nodes = iter.into_iter().map(|val| LinkCutNode {
+            ch: [NIL, NIL],
+            parent: NIL,
+            agg: val.clone(),
+            rev_agg: val.clone(),
+            val,
+            lazy: None,
+            rev: false,
+        }).collect();
+        Self { nodes, m, ml, u }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn is_splay_root(&self, x: usize) -> bool {
+        let p = self.nodes[x].parent;
+        p == NIL || !self.nodes[p].ch.contains(&x)
+    }
+
+    fn apply(&mut self, x: usize, lazy: &L) {
+        if x == NIL {
+            return;
+        }
+        let node = &mut self.nodes[x];
+        node.val = (self.u)(node.val.clone(), lazy.clone());
+        node.agg = (self.u)(node.agg.clone(), lazy.clone());
+        node.rev_agg = (self.u)(node.rev_agg.clone(), lazy.clone());
+        node.lazy = Some(match node.lazy.take() {
+            Some(old) => (self.ml)(old, lazy.clone()),
+            None => lazy.clone(),
+        });
+    }
+
+    fn reverse(&mut self, x: usize) {
+        if x == NIL {
+            return;
+        }
+        let node = &mut self.nodes[x];
+        node.ch.swap(0, 1);
+        std::mem::swap(&mut node.agg, &mut node.rev_agg);
+        node.rev = !node.rev;
+    }
+
+    fn push(&mut self, x: usize) {
+        let [a, b] = self.nodes[x].ch;
+        if std::mem::take(&mut self.nodes[x].rev) {
+            self.reverse(a);
+            self.reverse(b);
+        }
+        if let Some(lazy) = self.nodes[x].lazy.take() {
+            self.apply(a, &lazy);
+            self.apply(b, &lazy);
+        }
+    }
+
+    fn update(&mut self, x: usize) {
+        let [a, b] = self.nodes[x].ch;
+        let mut agg = self.nodes[x].val.clone();
+        let mut rev_agg = agg.clone();
+        if a != NIL {
+            agg = (self.m)(self.nodes[a].agg.clone(), agg);
+            rev_agg = (self.m)(rev_agg, self.nodes[a].rev_agg.clone());
+        }
+        if b != NIL {
+            agg = (self.m)(agg, self.nodes[b].agg.clone());
+            rev_agg = (self.m)(self.nodes[b].rev_agg.clone(), rev_agg);
+        }
+        self.nodes[x].agg = agg;
+        self.nodes[x].rev_agg = rev_agg;
+    }
+
+    fn rotate(&mut self, x: usize) {
+        let p = self.nodes[x].parent;
+        let g = self.nodes[p].parent;
+        let dir = (self.nodes[p].ch[1] == x) as usize;
+        let child = self.nodes[x].ch[1-dir];
+
+        if !self.is_splay_root(p) {
+            let pdir = (self.nodes[g].ch[1] == p) as usize;
+            self.nodes[g].ch[pdir] = x;
+        }
+        self.nodes[x].parent = g;
+
+        self.nodes[p].ch[dir] = child;
+        if child != NIL {
+            self.nodes[child].parent = p;
+        }
+        self.nodes[x].ch[1-dir] = p;
+        self.nodes[p].parent = x;
+
+        self.update(p);
+        self.update(x);
+    }
+
+    fn splay(&mut self, x: usize) {
+        let mut path = vec![x];
+        while !self.is_splay_root(*path.last().unwrap()) {
+            path.push(self.nodes[*path.last().unwrap()].parent);
+        }
+        for &y in path.iter().rev() {
+            self.push(y);
+        }
+
+        while !self.is_splay_root(x) {
+            let p = self.nodes[x].parent;
+            if !self.is_splay_root(p) {
+                let g = self.nodes[p].parent;
+                let zig_zig = (self.nodes[p].ch[1] == x) == (self.nodes[g].ch[1] == p);
+                self.rotate(if zig_zig { p } else { x });
+            }
+            self.rotate(x);
+        }
+    }
+
+    /// Makes the path from the root to `x` preferred, afterwards `x` is the root of its splay tree with no right child.
+    /// Returns the last vertex where the path switched trees, which is the lca when called after accessing another vertex.
+    fn access(&mut self, x: usize) -> usize {
+        let mut last = NIL;
+        let mut y = x;
+        while y != NIL {
+            self.splay(y);
+            self.nodes[y].ch[1] = last;
+            self.update(y);
+            last = y;
+            y = self.nodes[y].parent;
+        }
+        self.splay(x);
+        last
+    }
+
+    /// Makes `x` the root of its tree
+    pub fn make_root(&mut self, x: usize) {
+        self.access(x);
+        self.reverse(x);
+    }
+
+    /// Root of the tree containing `x`
+    pub fn find_root(&mut self, x: usize) -> usize {
+        self.access(x);
+        let mut y = x;
+        loop {
+            self.push(y);
+            match self.nodes[y].ch[0] {
+                NIL => break,
+                left => y = left,
+            }
+        }
+        self.splay(y);
+        y
+    }
+
+    pub fn connected(&mut self, x: usize, y: usize) -> bool {
+        x == y || self.find_root(x) == self.find_root(y)
+    }
+
+    /// Adds the edge `x - y`, returns false if they are already connected
+    pub fn link(&mut self, x: usize, y: usize) -> bool {
+        self.make_root(x);
+        if self.find_root(y) == x {
+            return false;
+        }
+        self.nodes[x].parent = y;
+        true
+    }
+
+    /// Removes the edge `x - y`, returns false if there is no such edge
+    pub fn cut(&mut self, x: usize, y: usize) -> bool {
+        self.make_root(x);
+        self.access(y);
+        if self.nodes[y].ch[0] != x || self.nodes[x].ch[1] != NIL {
+            return false;
+        }
+        self.nodes[y].ch[0] = NIL;
+        self.nodes[x].parent = NIL;
+        self.update(y);
+        true
+    }
+
+    /// Parent of `x` when the tree is rooted at its current root
+    pub fn parent(&mut self, x: usize) -> Option<usize> {
+        self.access(x);
+        let mut y = self.nodes[x].ch[0];
+        if y == NIL {
+            return None;
+        }
+        loop {
+            self.push(y);
+            match self.nodes[y].ch[1] {
+                NIL => break,
+                right => y = right,
+            }
+        }
+        self.splay(y);
+        Some(y)
+    }
+
+    /// Lowest common ancestor under the current root, `None` if not connected
+    pub fn lca(&mut self, x: usize, y: usize) -> Option<usize> {
+        if !self.connected(x, y) {
+            return None;
+        }
+        self.access(x);
+        Some(self.access(y))
+    }
+
+    pub fn get(&mut self, x: usize) -> D {
+        self.access(x);
+        self.nodes[x].val.clone()
+    }
+
+    pub fn set(&mut self, x: usize, val: D) {
+        self.access(x);
+        self.nodes[x].val = val;
+        self.update(x);
+    }
+
+    /// Aggregate of the values on the path from `x` to `y` in that order, `None` if not connected.
+    /// Makes `x` the root of its tree.
+    pub fn path_query(&mut self, x: usize, y: usize) -> Option<D> {
+        if !self.connected(x, y) {
+            return None;
+        }
+        self.make_root(x);
+        self.access(y);
+        Some(self.nodes[y].agg.clone())
+    }
+
+    /// Applies `lazy` to every vertex on the path from `x` to `y`, returns false if not connected.
+    /// Makes `x` the root of its tree.
+    pub fn path_update(&mut self, x: usize, y: usize, lazy: L) -> bool {
+        if !self.connected(x, y) {
+            return false;
+        }
+        self.make_root(x);
+        self.access(y);
+        self.apply(y, &lazy);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    struct Forest {
+        adj: Vec<Vec<usize>>,
+        vals: Vec<u64>,
+    }
+
+    impl Forest {
+        fn path(&self, x: usize, y: usize) -> Option<Vec<usize>> {
+            let mut prev = vec![usize::MAX; self.adj.len()];
+            let mut stack = vec![x];
+            prev[x] = x;
+            while let Some(v) = stack.pop() {
+                for &u in &self.adj[v] {
+                    if prev[u] == usize::MAX {
+                        prev[u] = v;
+                        stack.push(u);
+                    }
+                }
+            }
+            if prev[y] == usize::MAX {
+                return None;
+            }
+            let mut path = vec![y];
+            while *path.last().unwrap() != x {
+                path.push(prev[*path.last().unwrap()]);
+            }
+            path.reverse();
+            Some(path)
+        }
+    }
+
+    #[test]
+    fn brute() {
+        let mut rand = random::XorShift::new(19);
+        let n = 30;
+        let mut forest = Forest { adj: vec![vec![]; n], vals: (0..n as u64).collect() };
+        // (sum, size, polynomial hash of the order, P^size) with add on the path
+        const P: u64 = 1_000_003;
+        let hash = |a: (u64, u64, u64, u64), b: (u64, u64, u64, u64)| {
+            (a.0 + b.0, a.1 + b.1, a.2.wrapping_mul(b.3).wrapping_add(b.2), a.3.wrapping_mul(b.3))
+        };
+        let mut lct = LinkCutTree::from_iter(
+            (0..n as u64).map(|v| (v, 1, v, P)),
+            hash,
+            |a: u64, b: u64| a + b,
+            |d: (u64, u64, u64, u64), l: u64| (d.0 + d.1 * l, d.1, d.2, d.3),
+        );
+        let order_hash = |path: &[usize]| path.iter().fold(0u64, |acc, &v| acc.wrapping_mul(P).wrapping_add(v as u64));
+        for _ in 0..20000 {
+            let (x, y) = (rand.next() as usize % n, rand.next() as usize % n);
+            match rand.next() % 6 {
+                0 => {
+                    let linked = x != y && forest.path(x, y).is_none();
+                    assert_eq!(lct.link(x, y), linked);
+                    if linked {
+                        forest.adj[x].push(y);
+                        forest.adj[y].push(x);
+                    }
+                }
+                1 => {
+                    let has_edge = forest.adj[x].contains(&y);
+                    assert_eq!(lct.cut(x, y), has_edge);
+                    if has_edge {
+                        forest.adj[x].retain(|&v| v != y);
+                        forest.adj[y].retain(|&v| v != x);
+                    }
+                }
+                2 => {
+                    let path = forest.path(x, y);
+                    let expected = path.as_ref().map(|path| (path.iter().map(|&v| forest.vals[v]).sum::<u64>(), path.len() as u64, order_hash(path)));
+                    assert_eq!(lct.path_query(x, y).map(|d| (d.0, d.1, d.2)), expected);
+                }
+                3 => {
+                    let add = rand.next() % 10;
+                    let path = forest.path(x, y);
+                    assert_eq!(lct.path_update(x, y, add), path.is_some());
+                    for v in path.unwrap_or_default() {
+                        forest.vals[v] += add;
+                    }
+                }
+                4 => {
+                    assert_eq!(lct.connected(x, y), forest.path(x, y).is_some());
+                    assert_eq!(lct.get(x).0, forest.vals[x]);
+                }
+                _ => {
+                    // rooted at x, the lca of y and z is the last common vertex of their paths from x
+                    let z = rand.next() as usize % n;
+                    lct.make_root(x);
+                    match (forest.path(x, y), forest.path(x, z)) {
+                        (Some(py), Some(pz)) => {
+                            let expected = py.iter().zip(&pz).take_while(|(a, b)| a == b).last().map(|(a, _)| *a);
+                            assert_eq!(lct.lca(y, z), expected);
+                            assert_eq!(lct.parent(y), py.len().checked_sub(2).map(|i| py[i]));
+                            assert_eq!(lct.find_root(y), x);
+                        }
+                        _ => {
+                            if forest.path(y, z).is_none() {
+                                assert_eq!(lct.lca(y, z), None);
+                            }
+                            assert_ne!(lct.find_root(y) == x, forest.path(x, y).is_none());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_value() {
+        let mut lct = LinkCutTree::from_iter(vec![1, 2, 3], |a, b| a.max(b), |_, b| b, |_, l: i32| l);
+        assert!(lct.link(0, 1));
+        assert!(lct.link(1, 2));
+        assert_eq!(lct.path_query(0, 2), Some(3));
+        lct.set(2, 0);
+        assert_eq!(lct.path_query(2, 0), Some(2));
+        assert!(lct.path_update(0, 1, 7));
+        assert_eq!(lct.get(1), 7);
+        assert_eq!(lct.path_query(2, 2), Some(0));
+    }
+}
@@ -0,0 +1,388 @@
+use std::rc::Rc;
+
+struct PairingNode<T> {
+    value: T,
+    children: Vec<Box<PairingNode<T>>>,
+}
+
+/// Min-heap with O(1) push and meld and amortized O(log n) pop, use `std::cmp::Reverse` for a max-heap
+pub struct PairingHeap<T: Ord> {
+    root: Option<Box<PairingNode<T>>>,
+    len: usize,
+}
+
+impl<T: Ord> PairingHeap<T> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn link(mut a: Box<PairingNode<T>>, mut b: Box<PairingNode<T>>) -> Box<PairingNode<T>> {
+        if b.value < a.value {
+            std::mem::swap(&mut a, &mut b);
+        }
+        a.children.push(b);
+        a
+    }
+
+    fn link_option(a: Option<Box<PairingNode<T>>>, b: Option<Box<PairingNode<T>>>) -> Option<Box<PairingNode<T>>> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(Self::link(a, b)),
+            (a, None) => a,
+            (None, b) => b,
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        let node = Box::new(PairingNode { value, children: vec![] });
+        self.root = Self::link_option(self.root.take(), Some(node));
+        self.len += 1;
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| &root.value)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        let PairingNode { value, children } = *root;
+        // pair up children left to right, then merge the pairs right to left
+        let mut pairs = Vec::with_capacity(children.len().div_ceil(2));
+        let mut children = children.into_iter();
+        while let Some(a) = children.next() {
+            pairs.push(match children.next() {
+                Some(b) => Self::link(a, b),
+                None => a,
+            });
+        }
+        self.root = pairs.into_iter().rev().reduce(|acc, node| Self::link(node, acc));
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Moves all elements of `other` into `self` in O(1)
+    pub fn meld(&mut self, mut other: Self) {
+        self.root = Self::link_option(self.root.take(), other.root.take());
+        self.len += std::mem::take(&mut other.len);
+    }
+}
+
+impl<T: Ord> Default for PairingHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> Drop for PairingHeap<T> {
+    // the default recursive drop overflows the stack on deep trees
+    fn drop(&mut self) {
+        let mut stack = self.root.take().into_iter().collect::<Vec<_>>();
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.children);
+        }
+    }
+}
+
+struct LeftistNode<T> {
+    value: T,
+    // length of the right spine
+    rank: usize,
+    left: Option<Box<LeftistNode<T>>>,
+    right: Option<Box<LeftistNode<T>>>,
+}
+
+fn leftist_rank<T>(node: &Option<Box<LeftistNode<T>>>) -> usize {
+    node.as_ref().map_or(0, |node| node.rank)
+}
+
+/// Min-heap with O(log n) push, pop and meld, use `std::cmp::Reverse` for a max-heap
+pub struct LeftistHeap<T: Ord> {
+    root: Option<Box<LeftistNode<T>>>,
+    len: usize,
+}
+
+impl<T: Ord> LeftistHeap<T> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn merge(a: Option<Box<LeftistNode<T>>>, b: Option<Box<LeftistNode<T>>>) -> Option<Box<LeftistNode<T>>> {
+        let (mut a, mut b) = match (a, b) {
+            (Some(a), Some(b)) => (a, b),
+            (a, None) => return a,
+            (None, b) => return b,
+        };
+        if b.value < a.value {
+            std::mem::swap(&mut a, &mut b);
+        }
+        a.right = Self::merge(a.right.take(), Some(b));
+        if leftist_rank(&a.left) < leftist_rank(&a.right) {
+            std::mem::swap(&mut a.left, &mut a.right);
+        }
+        a.rank = leftist_rank(&a.right) + 1;
+        Some(a)
+    }
+
+    pub fn push(&mut self, value: T) {
+        let node = Box::new(LeftistNode { value, rank: 1, left: None, right: None });
+        self.root = Self::merge(self.root.take(), Some(node));
+        self.len += 1;
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| &root.value)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        let LeftistNode { value, left, right, .. } = *root;
+        self.root = Self::merge(left, right);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Moves all elements of `other` into `self` in O(log n)
+    pub fn meld(&mut self, mut other: Self) {
+        self.root = Self::merge(self.root.take(), other.root.take());
+        self.len += std::mem::take(&mut other.len);
+    }
+}
+
+impl<T: Ord> Default for LeftistHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> Drop for LeftistHeap<T> {
+    // the left spine can be linear, so the default recursive drop may overflow the stack
+    fn drop(&mut self) {
+        let mut stack = self.root.take().into_iter().collect::<Vec<_>>();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+struct PersistentNode<T> {
+    value: T,
+    rank: usize,
+    len: usize,
+    left: Option<Rc<PersistentNode<T>>>,
+    right: Option<Rc<PersistentNode<T>>>,
+}
+
+/// Immutable leftist min-heap, every operation returns a new heap sharing structure with the old one.
+/// Cloning is O(1), push, pop and meld are O(log n) and allocate O(log n) nodes.
+pub struct PersistentLeftistHeap<T: Ord + Clone> {
+    root: Option<Rc<PersistentNode<T>>>,
+}
+
+impl<T: Ord + Clone> PersistentLeftistHeap<T> {
+    pub fn new() -> Self {
+        Self { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    fn merge(a: &Option<Rc<PersistentNode<T>>>, b: &Option<Rc<PersistentNode<T>>>) -> Option<Rc<PersistentNode<T>>> {
+        let (mut a, mut b) = match (a, b) {
+            (Some(a), Some(b)) => (a, b),
+            (a, None) => return a.clone(),
+            (None, b) => return b.clone(),
+        };
+        if b.value < a.value {
+            std::mem::swap(&mut a, &mut b);
+        }
+        let mut left = a.left.clone();
+        let mut right = Self::merge(&a.right, &Some(b.clone()));
+        let rank = |node: &Option<Rc<PersistentNode<T>>>| node.as_ref().map_or(0, |node| node.rank);
+        if rank(&left) < rank(&right) {
+            std::mem::swap(&mut left, &mut right);
+        }
+        Some(Rc::new(PersistentNode {
+            value: a.value.clone(),
+            rank: rank(&right) + 1,
+            len: a.len + b.len,
+            left,
+            right,
+        }))
+    }
+
+    pub fn push(&self, value: T) -> Self {
+        let node = Rc::new(PersistentNode { value, rank: 1, len: 1, left: None, right: None });
+        Self { root: Self::merge(&self.root, &Some(node)) }
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| &root.value)
+    }
+
+    /// Returns the minimum and the heap without it
+    pub fn pop(&self) -> Option<(T, Self)> {
+        let root = self.root.as_ref()?;
+        Some((root.value.clone(), Self { root: Self::merge(&root.left, &root.right) }))
+    }
+
+    pub fn meld(&self, other: &Self) -> Self {
+        Self { root: Self::merge(&self.root, &other.root) }
+    }
+}
+
+impl<T: Ord + Clone> Clone for PersistentLeftistHeap<T> {
+    fn clone(&self) -> Self {
+        Self { root: self.root.clone() }
+    }
+}
+
+impl<T: Ord + Clone> Default for PersistentLeftistHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone> Drop for PersistentLeftistHeap<T> {
+    // only nodes no other heap shares are freed, one at a time to avoid deep recursion
+    fn drop(&mut self) {
+        let mut stack = self.root.take().into_iter().collect::<Vec<_>>();
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+    use std::{cmp::Reverse, collections::BinaryHeap};
+
+    #[test]
+    fn pairing_and_leftist_brute() {
+        let mut rand = random::XorShift::new(11);
+        let mut pairing = (0..4).map(|_| PairingHeap::new()).collect::<Vec<_>>();
+        let mut leftist = (0..4).map(|_| LeftistHeap::new()).collect::<Vec<_>>();
+        let mut reference = (0..4).map(|_| BinaryHeap::new()).collect::<Vec<_>>();
+        for _ in 0..5000 {
+            let i = rand.next() as usize % 4;
+            match rand.next() % 5 {
+                0 | 1 => {
+                    let v = rand.next() % 100;
+                    pairing[i].push(v);
+                    leftist[i].push(v);
+                    reference[i].push(Reverse(v));
+                }
+                2 => {
+                    let expected = reference[i].pop().map(|v| v.0);
+                    assert_eq!(pairing[i].pop(), expected);
+                    assert_eq!(leftist[i].pop(), expected);
+                }
+                3 => {
+                    let j = rand.next() as usize % 4;
+                    if i != j {
+                        let other = std::mem::take(&mut pairing[j]);
+                        pairing[i].meld(other);
+                        let other = std::mem::take(&mut leftist[j]);
+                        leftist[i].meld(other);
+                        let other = std::mem::take(&mut reference[j]);
+                        reference[i].extend(other);
+                    }
+                }
+                _ => {}
+            }
+            assert_eq!(pairing[i].peek().copied(), reference[i].peek().map(|v| v.0));
+            assert_eq!(leftist[i].peek().copied(), reference[i].peek().map(|v| v.0));
+            assert_eq!(pairing[i].len(), reference[i].len());
+            assert_eq!(leftist[i].len(), reference[i].len());
+        }
+    }
+
+    #[test]
+    fn persistent_versions() {
+        let mut rand = random::XorShift::new(12);
+        let mut versions = vec![(PersistentLeftistHeap::new(), vec![])];
+        for _ in 0..2000 {
+            let (heap, sorted) = versions[rand.next() as usize % versions.len()].clone();
+            let next = match rand.next() % 3 {
+                0 => {
+                    let v = rand.next() % 100;
+                    let mut sorted = sorted.clone();
+                    let pos = sorted.partition_point(|x| *x < v);
+                    sorted.insert(pos, v);
+                    (heap.push(v), sorted)
+                }
+                1 => match heap.pop() {
+                    Some((v, rest)) => {
+                        assert_eq!(v, sorted[0]);
+                        (rest, sorted[1..].to_vec())
+                    }
+                    None => {
+                        assert!(sorted.is_empty());
+                        continue;
+                    }
+                },
+                _ => {
+                    let (other, other_sorted) = &versions[rand.next() as usize % versions.len()];
+                    if sorted.len() + other_sorted.len() > 500 {
+                        continue;
+                    }
+                    let mut merged = sorted.iter().chain(other_sorted).copied().collect::<Vec<_>>();
+                    merged.sort();
+                    (heap.meld(other), merged)
+                }
+            };
+            assert_eq!(next.0.len(), next.1.len());
+            assert_eq!(next.0.peek(), next.1.first());
+            versions.push(next);
+        }
+        for (heap, sorted) in versions {
+            let mut heap = heap;
+            for v in sorted {
+                let (top, rest) = heap.pop().unwrap();
+                assert_eq!(top, v);
+                heap = rest;
+            }
+            assert!(heap.is_empty());
+        }
+    }
+
+    #[test]
+    fn deep_drop() {
+        let mut pairing = PairingHeap::new();
+        let mut leftist = LeftistHeap::new();
+        let mut persistent = PersistentLeftistHeap::new();
+        for v in (0..200000).rev() {
+            pairing.push(v);
+            leftist.push(v);
+            persistent = persistent.push(v);
+        }
+        assert_eq!(pairing.peek(), Some(&0));
+        assert_eq!(leftist.peek(), Some(&0));
+        assert_eq!(persistent.peek(), Some(&0));
+    }
+}
@@ -0,0 +1,118 @@
+use std::ops::{Bound, Range, RangeBounds};
+
+use super::to_range;
+
+/// Segment tree storing the sorted elements of every node, counts values in a range in O(log^2 n)
+pub struct MergeSortTree<T: Ord + Clone> {
+    n: usize,
+    // bottom-up layout, data[n+i] holds element i
+    data: Vec<Vec<T>>,
+}
+
+impl<T: Ord + Clone> MergeSortTree<T> {
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>) -> Self {
+        let values = iter.into_iter().collect::<Vec<_>>();
+        let n = values.len();
+        let mut data = vec![vec![]; n];
+        data.extend(values.into_iter().map(|v| vec![v]));
+        for i in (1..n).rev() {
+            let (a, b) = (&data[i*2], &data[i*2+1]);
+            let mut merged = Vec::with_capacity(a.len()+b.len());
+            let (mut x, mut y) = (0, 0);
+            while x < a.len() || y < b.len() {
+                if y == b.len() || (x < a.len() && a[x] <= b[y]) {
+                    merged.push(a[x].clone());
+                    x += 1;
+                }
+                else {
+                    merged.push(b[y].clone());
+                    y += 1;
+                }
+            }
+            data[i] = merged;
+        }
+        Self { n, data }
+    }
+
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Sums `f(node)` over the O(log n) nodes covering the range
+    fn fold(&self, range: impl RangeBounds<usize>, f: impl Fn(&Vec<T>) -> usize) -> usize {
+        let Range { start: l, end: r } = to_range(range, self.n);
+        let (mut l, mut r) = (l+self.n, r.max(l)+self.n);
+        let mut result = 0;
+        while l < r {
+            if l % 2 == 1 {
+                result += f(&self.data[l]);
+                l += 1;
+            }
+            if r % 2 == 1 {
+                r -= 1;
+                result += f(&self.data[r]);
+            }
+            l /= 2;
+            r /= 2;
+        }
+        result
+    }
+
+    /// Number of values less than `x` in the range
+    pub fn count_less(&self, range: impl RangeBounds<usize>, x: &T) -> usize {
+        self.fold(range, |node| node.partition_point(|v| v < x))
+    }
+
+    /// Number of values less than or equal to `x` in the range
+    pub fn count_less_eq(&self, range: impl RangeBounds<usize>, x: &T) -> usize {
+        self.fold(range, |node| node.partition_point(|v| v <= x))
+    }
+
+    /// Number of values within `values` in the range
+    pub fn count_range(&self, range: impl RangeBounds<usize>, values: impl RangeBounds<T>) -> usize {
+        self.fold(range, |node| {
+            let end = match values.end_bound() {
+                Bound::Included(x) => node.partition_point(|v| v <= x),
+                Bound::Excluded(x) => node.partition_point(|v| v < x),
+                Bound::Unbounded => node.len(),
+            };
+            let start = match values.start_bound() {
+                Bound::Included(x) => node.partition_point(|v| v < x),
+                Bound::Excluded(x) => node.partition_point(|v| v <= x),
+                Bound::Unbounded => 0,
+            };
+            end.saturating_sub(start)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    #[test]
+    fn count_brute() {
+        let mut rand = random::XorShift::new(5);
+        for n in [0, 1, 7, 64, 100] {
+            let arr = (0..n).map(|_| rand.next() % 20).collect::<Vec<_>>();
+            let mst = MergeSortTree::new_from_iter(arr.clone());
+            assert_eq!(mst.len(), n);
+            for l in 0..=n {
+                for r in l..=n {
+                    let x = rand.next() % 22;
+                    let y = rand.next() % 22;
+                    assert_eq!(mst.count_less(l..r, &x), arr[l..r].iter().filter(|v| **v < x).count());
+                    assert_eq!(mst.count_less_eq(l..r, &x), arr[l..r].iter().filter(|v| **v <= x).count());
+                    assert_eq!(mst.count_range(l..r, x..y), arr[l..r].iter().filter(|v| (x..y).contains(*v)).count());
+                    assert_eq!(mst.count_range(l..r, x..=y), arr[l..r].iter().filter(|v| (x..=y).contains(*v)).count());
+                }
+            }
+            assert_eq!(mst.count_range(.., ..), n);
+        }
+    }
+}
@@ -0,0 +1,388 @@
+/// Window state maintained by Mo's algorithm, queries are answered by moving the window one element at a time
+pub trait MoState {
+    type Answer;
+
+    /// Adds element `i` to the window
+    fn add(&mut self, i: usize);
+    /// Removes element `i` from the window
+    fn remove(&mut self, i: usize);
+    fn answer(&self) -> Self::Answer;
+}
+
+/// Window state for Mo's algorithm with point updates
+pub trait MoUpdateState: MoState {
+    /// Applies update `t` while the window is `[l, r)`
+    fn apply(&mut self, t: usize, l: usize, r: usize);
+    /// Reverts update `t` while the window is `[l, r)`
+    fn revert(&mut self, t: usize, l: usize, r: usize);
+}
+
+/// Position of `(x, y)` along the Hilbert curve over a `2^log x 2^log` grid
+fn hilbert_order(x: usize, y: usize, log: u32) -> u64 {
+    let n = 1u64 << log;
+    let (mut x, mut y) = (x as u64, y as u64);
+    let mut d = 0;
+    let mut s = n/2;
+    while s > 0 {
+        let rx = (x & s > 0) as u64;
+        let ry = (y & s > 0) as u64;
+        d += s * s * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = n-1 - x;
+                y = n-1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+/// Moves the window `[l, r)` to `[ql, qr)`
+fn move_window<S: MoState>(state: &mut S, (l, r): (&mut usize, &mut usize), (ql, qr): (usize, usize)) {
+    assert!(ql <= qr);
+    while *l > ql {
+        *l -= 1;
+        state.add(*l);
+    }
+    while *r < qr {
+        state.add(*r);
+        *r += 1;
+    }
+    while *l < ql {
+        state.remove(*l);
+        *l += 1;
+    }
+    while *r > qr {
+        *r -= 1;
+        state.remove(*r);
+    }
+}
+
+fn run<S: MoState, A>(state: &mut S, queries: &[(usize, usize)], mut answer: impl FnMut(&mut S, usize) -> A) -> Vec<A> {
+    let max = queries.iter().map(|&(_, r)| r).max().unwrap_or(0);
+    let log = (max+1).next_power_of_two().trailing_zeros();
+    let mut order = (0..queries.len()).collect::<Vec<_>>();
+    order.sort_by_cached_key(|&i| hilbert_order(queries[i].0, queries[i].1, log));
+
+    let mut result = (0..queries.len()).map(|_| None).collect::<Vec<_>>();
+    let (mut l, mut r) = (0, 0);
+    for i in order {
+        move_window(state, (&mut l, &mut r), queries[i]);
+        result[i] = Some(answer(state, i));
+    }
+    move_window(state, (&mut l, &mut r), (0, 0));
+    result.into_iter().map(Option::unwrap).collect()
+}
+
+/// Answers half-open range queries `[l, r)` offline in O(n sqrt q) window moves, ordered along the Hilbert curve.
+/// The window is emptied again before returning.
+pub fn mo<S: MoState>(state: &mut S, queries: &[(usize, usize)]) -> Vec<S::Answer> {
+    run(state, queries, |state, _| state.answer())
+}
+
+/// Answers queries `(l, r, t)` on `[l, r)` after the first `t` updates in O(n^(5/3)) moves.
+/// The window is emptied and all updates are reverted before returning.
+pub fn mo_with_updates<S: MoUpdateState>(state: &mut S, queries: &[(usize, usize, usize)]) -> Vec<S::Answer> {
+    let max = queries.iter().map(|&(_, r, _)| r).max().unwrap_or(0);
+    let block = ((max as f64).powf(2.0 / 3.0) as usize).max(1);
+    let mut order = (0..queries.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| {
+        let (l, r, t) = queries[i];
+        (l / block, r / block, t)
+    });
+
+    let mut result = (0..queries.len()).map(|_| None).collect::<Vec<_>>();
+    let (mut l, mut r, mut time) = (0, 0, 0);
+    for i in order {
+        let (ql, qr, t) = queries[i];
+        move_window(state, (&mut l, &mut r), (ql, qr));
+        while time < t {
+            state.apply(time, l, r);
+            time += 1;
+        }
+        while time > t {
+            time -= 1;
+            state.revert(time, l, r);
+        }
+        result[i] = Some(state.answer());
+    }
+    move_window(state, (&mut l, &mut r), (0, 0));
+    while time > 0 {
+        time -= 1;
+        state.revert(time, 0, 0);
+    }
+    result.into_iter().map(Option::unwrap).collect()
+}
+
+/// Adapts a vertex state to positions of the euler tour, a vertex is in the window if it occurs there exactly once
+struct TreeWindow<'a, S: MoState> {
+    state: &'a mut S,
+    tour: Vec<usize>,
+    active: Vec<bool>,
+}
+
+impl<S: MoState> TreeWindow<'_, S> {
+    fn toggle(&mut self, v: usize) {
+        if self.active[v] {
+            self.state.remove(v);
+        }
+        else {
+            self.state.add(v);
+        }
+        self.active[v] = !self.active[v];
+    }
+}
+
+impl<S: MoState> MoState for TreeWindow<'_, S> {
+    type Answer = S::Answer;
+
+    fn add(&mut self, i: usize) {
+        self.toggle(self.tour[i]);
+    }
+    fn remove(&mut self, i: usize) {
+        self.toggle(self.tour[i]);
+    }
+    fn answer(&self) -> Self::Answer {
+        self.state.answer()
+    }
+}
+
+/// Answers queries on the vertices of the path between `u` and `v` (both inclusive) of a tree given by adjacency lists.
+/// `state` receives vertex ids.
+pub fn mo_on_tree<S: MoState>(state: &mut S, adj: &[Vec<usize>], root: usize, queries: &[(usize, usize)]) -> Vec<S::Answer> {
+    let n = adj.len();
+    let mut tin = vec![0; n];
+    let mut tout = vec![0; n];
+    let mut tour = Vec::with_capacity(2*n);
+    let log = n.next_power_of_two().trailing_zeros() as usize + 1;
+    // up[k][v] = 2^k-th ancestor of v, the root is its own parent
+    let mut up = vec![vec![root; n]; log];
+    let mut depth = vec![0; n];
+
+    let mut stack = vec![(root, 0)];
+    tour.push(root);
+    while let Some((v, next)) = stack.last_mut() {
+        let v = *v;
+        if let Some(&u) = adj[v].get(*next) {
+            *next += 1;
+            if u != up[0][v] {
+                up[0][u] = v;
+                depth[u] = depth[v]+1;
+                tin[u] = tour.len();
+                tour.push(u);
+                stack.push((u, 0));
+            }
+        }
+        else {
+            tout[v] = tour.len();
+            tour.push(v);
+            stack.pop();
+        }
+    }
+    for k in 1..log {
+        for v in 0..n {
+            up[k][v] = up[k-1][up[k-1][v]];
+        }
+    }
+    let lca = |mut u: usize, mut v: usize| {
+        if depth[u] < depth[v] {
+            std::mem::swap(&mut u, &mut v);
+        }
+        for k in (0..log).rev() {
+            if depth[u] - depth[v] >= 1 << k {
+                u = up[k][u];
+            }
+        }
+        if u == v {
+            return u;
+        }
+        for k in (0..log).rev() {
+            if up[k][u] != up[k][v] {
+                u = up[k][u];
+                v = up[k][v];
+            }
+        }
+        up[0][u]
+    };
+
+    let mut ranges = Vec::with_capacity(queries.len());
+    let mut extra = Vec::with_capacity(queries.len());
+    for &(u, v) in queries {
+        let (u, v) = if tin[u] <= tin[v] { (u, v) } else { (v, u) };
+        let w = lca(u, v);
+        if w == u {
+            ranges.push((tin[u], tin[v]+1));
+            extra.push(None);
+        }
+        else {
+            ranges.push((tout[u], tin[v]+1));
+            extra.push(Some(w));
+        }
+    }
+
+    let mut window = TreeWindow { state, tour, active: vec![false; n] };
+    run(&mut window, &ranges, |window, i| {
+        let Some(w) = extra[i] else {
+            return window.answer();
+        };
+        window.toggle(w);
+        let result = window.answer();
+        window.toggle(w);
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    struct Distinct {
+        values: Vec<usize>,
+        cnt: Vec<usize>,
+        distinct: usize,
+        updates: Vec<(usize, usize)>,
+    }
+
+    impl Distinct {
+        fn new(values: Vec<usize>, updates: Vec<(usize, usize)>) -> Self {
+            Self { values, cnt: vec![0; 100], distinct: 0, updates }
+        }
+
+        fn swap_update(&mut self, t: usize, l: usize, r: usize) {
+            let (pos, _) = self.updates[t];
+            let inside = l <= pos && pos < r;
+            if inside {
+                self.remove(pos);
+            }
+            std::mem::swap(&mut self.values[pos], &mut self.updates[t].1);
+            if inside {
+                self.add(pos);
+            }
+        }
+    }
+
+    impl MoState for Distinct {
+        type Answer = usize;
+
+        fn add(&mut self, i: usize) {
+            self.cnt[self.values[i]] += 1;
+            if self.cnt[self.values[i]] == 1 {
+                self.distinct += 1;
+            }
+        }
+        fn remove(&mut self, i: usize) {
+            self.cnt[self.values[i]] -= 1;
+            if self.cnt[self.values[i]] == 0 {
+                self.distinct -= 1;
+            }
+        }
+        fn answer(&self) -> usize {
+            self.distinct
+        }
+    }
+
+    impl MoUpdateState for Distinct {
+        fn apply(&mut self, t: usize, l: usize, r: usize) {
+            self.swap_update(t, l, r);
+        }
+        fn revert(&mut self, t: usize, l: usize, r: usize) {
+            self.swap_update(t, l, r);
+        }
+    }
+
+    fn distinct(values: impl Iterator<Item=usize>) -> usize {
+        values.collect::<std::collections::HashSet<_>>().len()
+    }
+
+    #[test]
+    fn range_distinct() {
+        let mut rand = random::XorShift::new(6);
+        let arr = (0..300).map(|_| rand.next() as usize % 30).collect::<Vec<_>>();
+        let queries = (0..500).map(|_| {
+            let l = rand.next() as usize % 301;
+            (l, l + rand.next() as usize % (301 - l))
+        }).collect::<Vec<_>>();
+        let mut state = Distinct::new(arr.clone(), vec![]);
+        let answers = mo(&mut state, &queries);
+        for (&(l, r), ans) in queries.iter().zip(answers) {
+            assert_eq!(ans, distinct(arr[l..r].iter().copied()));
+        }
+        assert_eq!(state.distinct, 0);
+        assert!(mo(&mut state, &[]).is_empty());
+    }
+
+    #[test]
+    fn range_distinct_with_updates() {
+        let mut rand = random::XorShift::new(7);
+        let mut arr = (0..200).map(|_| rand.next() as usize % 20).collect::<Vec<_>>();
+        let init = arr.clone();
+        let mut updates = vec![];
+        let mut queries = vec![];
+        let mut expected = vec![];
+        for _ in 0..600 {
+            if rand.next().is_multiple_of(3) {
+                let (pos, val) = (rand.next() as usize % 200, rand.next() as usize % 20);
+                updates.push((pos, val));
+                arr[pos] = val;
+            }
+            else {
+                let l = rand.next() as usize % 201;
+                let r = l + rand.next() as usize % (201 - l);
+                queries.push((l, r, updates.len()));
+                expected.push(distinct(arr[l..r].iter().copied()));
+            }
+        }
+        let mut state = Distinct::new(init.clone(), updates);
+        assert_eq!(mo_with_updates(&mut state, &queries), expected);
+        assert_eq!(state.values, init);
+    }
+
+    #[test]
+    fn path_distinct() {
+        let mut rand = random::XorShift::new(8);
+        let n = 150;
+        let mut adj = vec![vec![]; n];
+        let mut parent = vec![0; n];
+        for v in 1..n {
+            let p = rand.next() as usize % v;
+            parent[v] = p;
+            adj[v].push(p);
+            adj[p].push(v);
+        }
+        let colors = (0..n).map(|_| rand.next() as usize % 15).collect::<Vec<_>>();
+        let queries = (0..400).map(|_| (rand.next() as usize % n, rand.next() as usize % n)).collect::<Vec<_>>();
+
+        let mut state = Distinct::new(colors.clone(), vec![]);
+        let answers = mo_on_tree(&mut state, &adj, 0, &queries);
+        let depth = |mut v: usize| {
+            let mut d = 0;
+            while v != 0 {
+                v = parent[v];
+                d += 1;
+            }
+            d
+        };
+        for (&(mut u, mut v), ans) in queries.iter().zip(answers) {
+            let mut path = vec![];
+            while depth(u) > depth(v) {
+                path.push(u);
+                u = parent[u];
+            }
+            while depth(v) > depth(u) {
+                path.push(v);
+                v = parent[v];
+            }
+            while u != v {
+                path.push(u);
+                path.push(v);
+                u = parent[u];
+                v = parent[v];
+            }
+            path.push(u);
+            assert_eq!(ans, distinct(path.into_iter().map(|v| colors[v])));
+        }
+    }
+}
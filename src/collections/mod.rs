@@ -1,4 +1,42 @@
 pub mod disjoint_set;
 pub mod treap;
 pub mod sparse_table;
-pub mod seg_tree;
\ No newline at end of file
+pub mod seg_tree;
+pub mod seg_tree_beats;
+pub mod li_chao_tree;
+pub mod convex_hull_trick;
+pub mod fenwick_tree;
+pub mod seq_treap;
+pub mod arena_treap;
+pub mod treap_map;
+pub mod wavelet_matrix;
+pub mod merge_sort_tree;
+pub mod mo;
+pub mod sqrt_decomposition;
+pub mod meldable_heap;
+pub mod indexed_heap;
+pub mod radix_heap;
+pub mod swag;
+pub mod link_cut_tree;
+pub mod euler_tour_tree;
+pub mod bit_set;
+pub mod compressor;
+pub mod cartesian_tree;
+pub mod fast_hash;
+
+use std::ops::{Bound, Range, RangeBounds};
+
+/// Converts index range bounds to a half-open range, unbounded ends are `0` and `n`
+pub(crate) fn to_range(range_bounds: impl RangeBounds<usize>, n: usize) -> Range<usize> {
+    let start = match range_bounds.start_bound() {
+        Bound::Included(&p) => p,
+        Bound::Excluded(&p) => p + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range_bounds.end_bound() {
+        Bound::Included(&p) => p + 1,
+        Bound::Excluded(&p) => p,
+        Bound::Unbounded => n,
+    };
+    start..end
+}
@@ -0,0 +1,187 @@
+use std::collections::VecDeque;
+
+use crate::math::Integer;
+
+/// Monotone min-heap for integer keys, every pushed key must be at least the last popped one.
+/// Each element moves between buckets O(log C) times, where C is the key range.
+pub struct RadixHeap<K: Integer, V> {
+    // buckets[i] holds keys whose highest bit differing from `last` is bit i-1, buckets[0] keys equal to `last`
+    buckets: Vec<Vec<(K, V)>>,
+    last: u64,
+    len: usize,
+}
+
+impl<K: Integer, V> RadixHeap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            buckets: (0..=64).map(|_| vec![]).collect(),
+            last: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Order preserving map of the key to u64
+    fn ord(key: K) -> u64 {
+        let signed = K::from_i64(-1) < K::ZERO;
+        key.to_i64() as u64 ^ if signed { 1 << 63 } else { 0 }
+    }
+
+    fn bucket(&self, ord: u64) -> usize {
+        64 - (ord ^ self.last).leading_zeros() as usize
+    }
+
+    /// Panics if `key` is less than the last popped key
+    pub fn push(&mut self, key: K, value: V) {
+        let ord = Self::ord(key);
+        assert!(ord >= self.last, "key is less than the last popped key");
+        let b = self.bucket(ord);
+        self.buckets[b].push((key, value));
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        if self.buckets[0].is_empty() {
+            let i = self.buckets.iter().position(|bucket| !bucket.is_empty())?;
+            let moved = std::mem::take(&mut self.buckets[i]);
+            self.last = moved.iter().map(|&(key, _)| Self::ord(key)).min().unwrap();
+            for (key, value) in moved {
+                let b = self.bucket(Self::ord(key));
+                self.buckets[b].push((key, value));
+            }
+        }
+        self.len -= 1;
+        self.buckets[0].pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(Vec::clear);
+        self.last = 0;
+        self.len = 0;
+    }
+}
+
+impl<K: Integer, V> Default for RadixHeap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shortest distances from `source` in a graph with edge weights 0 or 1 in O(n + m), `None` for unreachable vertices
+pub fn zero_one_bfs(adj: &[Vec<(usize, u8)>], source: usize) -> Vec<Option<u64>> {
+    let mut dist = vec![None; adj.len()];
+    let mut deque = VecDeque::new();
+    dist[source] = Some(0);
+    deque.push_back((source, 0));
+    while let Some((u, d)) = deque.pop_front() {
+        if dist[u] != Some(d) {
+            continue;
+        }
+        for &(v, w) in &adj[u] {
+            assert!(w <= 1, "edge weights must be 0 or 1");
+            let nd = d + w as u64;
+            if dist[v].is_none_or(|old| nd < old) {
+                dist[v] = Some(nd);
+                if w == 0 {
+                    deque.push_front((v, nd));
+                }
+                else {
+                    deque.push_back((v, nd));
+                }
+            }
+        }
+    }
+    dist
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+    use std::{cmp::Reverse, collections::BinaryHeap};
+
+    #[test]
+    fn monotone_brute() {
+        let mut rand = random::XorShift::new(15);
+        let mut heap = RadixHeap::new();
+        let mut reference = BinaryHeap::new();
+        let mut last = 0u64;
+        for i in 0..20000 {
+            if !rand.next().is_multiple_of(3) {
+                let key = last + rand.next() % 1000;
+                heap.push(key, i);
+                reference.push(Reverse(key));
+            }
+            else {
+                let popped = heap.pop();
+                assert_eq!(popped.map(|(key, _)| key), reference.pop().map(|key| key.0));
+                if let Some((key, _)) = popped {
+                    last = key;
+                }
+            }
+            assert_eq!(heap.len(), reference.len());
+        }
+    }
+
+    #[test]
+    fn signed_keys() {
+        let mut heap = RadixHeap::new();
+        for key in [5i64, -3, i64::MIN, 0, i64::MAX, -3] {
+            heap.push(key, ());
+        }
+        let mut popped = vec![];
+        while let Some((key, ())) = heap.pop() {
+            popped.push(key);
+        }
+        assert_eq!(popped, vec![i64::MIN, -3, -3, 0, 5, i64::MAX]);
+
+        let mut heap = RadixHeap::new();
+        heap.push(u64::MAX, 'a');
+        heap.push(1, 'b');
+        assert_eq!(heap.pop(), Some((1, 'b')));
+        assert_eq!(heap.pop(), Some((u64::MAX, 'a')));
+        assert_eq!(heap.pop(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn non_monotone_push() {
+        let mut heap = RadixHeap::new();
+        heap.push(10u32, ());
+        heap.pop();
+        heap.push(9, ());
+    }
+
+    #[test]
+    fn zero_one_bfs_brute() {
+        let mut rand = random::XorShift::new(16);
+        let n = 100;
+        let mut adj = vec![vec![]; n];
+        for _ in 0..300 {
+            let (u, v) = (rand.next() as usize % n, rand.next() as usize % n);
+            adj[u].push((v, (rand.next() % 2) as u8));
+        }
+        let mut expected = vec![None; n];
+        expected[0] = Some(0u64);
+        for _ in 0..n {
+            for u in 0..n {
+                let Some(d) = expected[u] else {
+                    continue;
+                };
+                for &(v, w) in &adj[u] {
+                    if expected[v].is_none_or(|old| (d + w as u64) < old) {
+                        expected[v] = Some(d + w as u64);
+                    }
+                }
+            }
+        }
+        assert_eq!(zero_one_bfs(&adj, 0), expected);
+    }
+}
@@ -1,5 +1,7 @@
 use std::ops::{Range, RangeBounds};
 
+use super::to_range;
+
 pub struct SegTree<D: Clone, F: Fn(D, D) -> D> {
     n: usize,
     data: Vec<Option<D>>,
@@ -48,17 +50,7 @@ impl<D: Clone, M: Fn(D, D) -> D> SegTree<D, M> {
     }
 
     fn to_range(&self, range_bounds: impl RangeBounds<usize>) -> Range<usize> {
-        let start = match range_bounds.start_bound() {
-            std::ops::Bound::Included(&p) => p,
-            std::ops::Bound::Excluded(&p) => p + 1,
-            std::ops::Bound::Unbounded => 0,
-        };
-        let end = match range_bounds.end_bound() {
-            std::ops::Bound::Included(&p) => p + 1,
-            std::ops::Bound::Excluded(&p) => p,
-            std::ops::Bound::Unbounded => self.n,
-        };
-        start..end
+        to_range(range_bounds, self.n)
     }
 
     fn query_inner(&self, l: usize, r: usize, ti: usize, tl: usize, tr: usize) -> Option<D> {
@@ -176,17 +168,7 @@ impl<D: Clone, L: Clone, M: Fn(D, D) -> D, ML: Fn(L, L) -> L, U: Fn(D, L) -> D>
     }
 
     fn to_range(&self, range_bounds: impl RangeBounds<usize>) -> Range<usize> {
-        let start = match range_bounds.start_bound() {
-            std::ops::Bound::Included(&p) => p,
-            std::ops::Bound::Excluded(&p) => p + 1,
-            std::ops::Bound::Unbounded => 0,
-        };
-        let end = match range_bounds.end_bound() {
-            std::ops::Bound::Included(&p) => p + 1,
-            std::ops::Bound::Excluded(&p) => p,
-            std::ops::Bound::Unbounded => self.n,
-        };
-        start..end
+        to_range(range_bounds, self.n)
     }
 
     fn push(&mut self, ti: usize) {
@@ -272,6 +254,143 @@ impl<D: Clone, L: Clone, M: Fn(D, D) -> D, ML: Fn(L, L) -> L, U: Fn(D, L) -> D>
 }
 
 
+/// Point update and rectangle query, `m` has to be associative and commutative
+pub struct SegTree2D<D: Clone, M: Fn(D, D) -> D> {
+    n: usize,
+    k: usize,
+    // data[ti][tj] covers rows of the outer node ti and columns of the inner node tj
+    data: Vec<Vec<Option<D>>>,
+    m: M
+}
+
+impl<D: Clone, M: Fn(D, D) -> D> SegTree2D<D, M> {
+    pub fn new(n: usize, k: usize, m: M) -> Self {
+        Self {
+            data: vec![vec![None; k*4]; n*4],
+            n,
+            k,
+            m
+        }
+    }
+
+    pub fn from_iter<R: IntoIterator<Item=D>, I: IntoIterator<Item=R>>(iter: I, m: M) -> Self {
+        let grid = iter.into_iter().map(|row| row.into_iter().map(Some).collect::<Vec<_>>()).collect::<Vec<_>>();
+        let k = grid.first().map_or(0, |row| row.len());
+        assert!(grid.iter().all(|row| row.len() == k));
+        let mut result = Self::new(grid.len(), k, m);
+        if result.n > 0 && result.k > 0 {
+            result.build(&grid, 1, 0, grid.len());
+        }
+        result
+    }
+
+    fn build(&mut self, grid: &Vec<Vec<Option<D>>>, ti: usize, tl: usize, tr: usize) {
+        if tl+1 == tr {
+            self.build_row(&grid[tl], ti, 1, 0, self.k);
+            return;
+        }
+        let tm = (tl+tr)/2;
+        self.build(grid, ti*2, tl, tm);
+        self.build(grid, ti*2+1, tm, tr);
+        for tj in 0..self.k*4 {
+            self.data[ti][tj] = self.merge(self.data[ti*2][tj].clone(), self.data[ti*2+1][tj].clone());
+        }
+    }
+
+    fn build_row(&mut self, row: &Vec<Option<D>>, ti: usize, tj: usize, cl: usize, cr: usize) {
+        if cl+1 == cr {
+            self.data[ti][tj] = row[cl].clone();
+            return;
+        }
+        let cm = (cl+cr)/2;
+        self.build_row(row, ti, tj*2, cl, cm);
+        self.build_row(row, ti, tj*2+1, cm, cr);
+        self.data[ti][tj] = self.merge(self.data[ti][tj*2].clone(), self.data[ti][tj*2+1].clone());
+    }
+
+    fn merge(&self, a: Option<D>, b: Option<D>) -> Option<D> {
+        let Some(a) = a else {
+            return b;
+        };
+        let Some(b) = b else {
+            return Some(a);
+        };
+        Some((self.m)(a, b))
+    }
+
+    fn query_row(&self, ti: usize, l: usize, r: usize, tj: usize, cl: usize, cr: usize) -> Option<D> {
+        if l >= r {
+            return None;
+        }
+        if cl == l && cr == r {
+            return self.data[ti][tj].clone();
+        }
+        let cm = (cl+cr)/2;
+        let left_res = self.query_row(ti, l, r.min(cm), tj*2, cl, cm);
+        let right_res = self.query_row(ti, l.max(cm), r, tj*2+1, cm, cr);
+        self.merge(left_res, right_res)
+    }
+
+    fn query_inner(&self, rows: &Range<usize>, cols: &Range<usize>, ti: usize, tl: usize, tr: usize) -> Option<D> {
+        if rows.start >= rows.end {
+            return None;
+        }
+        if tl == rows.start && tr == rows.end {
+            return self.query_row(ti, cols.start, cols.end, 1, 0, self.k);
+        }
+        let tm = (tl+tr)/2;
+        let left_res = self.query_inner(&(rows.start..rows.end.min(tm)), cols, ti*2, tl, tm);
+        let right_res = self.query_inner(&(rows.start.max(tm)..rows.end), cols, ti*2+1, tm, tr);
+        self.merge(left_res, right_res)
+    }
+
+    /// Returns `None` for an empty rectangle or one without any set cells
+    pub fn query(&self, rows: impl RangeBounds<usize>, cols: impl RangeBounds<usize>) -> Option<D> {
+        let rows = to_range(rows, self.n);
+        let cols = to_range(cols, self.k);
+        self.query_inner(&rows, &cols, 1, 0, self.n)
+    }
+
+    /// Sets the column `j` of the row node `ti`, `val` is `Some` only for leaf rows,
+    /// other rows are recomputed from their children
+    fn set_row(&mut self, ti: usize, j: usize, val: &Option<D>, tj: usize, cl: usize, cr: usize) {
+        if cl+1 == cr {
+            self.data[ti][tj] = match val {
+                Some(_) => val.clone(),
+                None => self.merge(self.data[ti*2][tj].clone(), self.data[ti*2+1][tj].clone()),
+            };
+            return;
+        }
+        let cm = (cl+cr)/2;
+        if j < cm {
+            self.set_row(ti, j, val, tj*2, cl, cm);
+        }
+        else {
+            self.set_row(ti, j, val, tj*2+1, cm, cr);
+        }
+        self.data[ti][tj] = self.merge(self.data[ti][tj*2].clone(), self.data[ti][tj*2+1].clone());
+    }
+
+    fn set_inner(&mut self, i: usize, j: usize, val: D, ti: usize, tl: usize, tr: usize) {
+        if tl+1 == tr {
+            self.set_row(ti, j, &Some(val), 1, 0, self.k);
+            return;
+        }
+        let tm = (tl+tr)/2;
+        if i < tm {
+            self.set_inner(i, j, val, ti*2, tl, tm);
+        }
+        else {
+            self.set_inner(i, j, val, ti*2+1, tm, tr);
+        }
+        self.set_row(ti, j, &None, 1, 0, self.k);
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, val: D) {
+        self.set_inner(i, j, val, 1, 0, self.n);
+    }
+}
+
 mod seg_tree_tests {
     use super::SegTree;
 
@@ -396,3 +515,51 @@ mod lazy_seg_tree_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod seg_tree_2d_tests {
+    use crate::random::XorShift;
+
+    use super::SegTree2D;
+
+    #[test]
+    fn query_brute() {
+        let mut rand = XorShift::new(8);
+        let (n, k) = (12, 17);
+        let mut grid = (0..n).map(|_| (0..k).map(|_| rand.next()%1000).collect::<Vec<_>>()).collect::<Vec<_>>();
+        let mut st = SegTree2D::from_iter(grid.clone(), |a, b| a+b);
+
+        for _ in 0..2000 {
+            let (i, j) = (rand.next() as usize % n, rand.next() as usize % k);
+            let val = rand.next()%1000;
+            st.set(i, j, val);
+            grid[i][j] = val;
+
+            let (mut r1, mut r2) = (rand.next() as usize % n, rand.next() as usize % n);
+            let (mut c1, mut c2) = (rand.next() as usize % k, rand.next() as usize % k);
+            if r1 > r2 {
+                std::mem::swap(&mut r1, &mut r2);
+            }
+            if c1 > c2 {
+                std::mem::swap(&mut c1, &mut c2);
+            }
+            let tar = grid[r1..=r2].iter().map(|row| row[c1..=c2].iter().sum::<u64>()).sum::<u64>();
+            assert_eq!(st.query(r1..=r2, c1..=c2), Some(tar));
+        }
+        assert_eq!(st.query(.., ..), Some(grid.iter().flatten().sum::<u64>()));
+        assert_eq!(st.query(2..2, ..), None);
+        assert_eq!(st.query(.., 3..3), None);
+    }
+
+    #[test]
+    fn set_on_empty() {
+        let mut st = SegTree2D::new(3, 4, |a: i32, b: i32| a.max(b));
+        st.set(1, 2, 5);
+        st.set(2, 0, 7);
+        assert_eq!(st.query(.., ..), Some(7));
+        assert_eq!(st.query(0..2, ..), Some(5));
+        assert_eq!(st.query(1..=1, 2..=2), Some(5));
+        assert_eq!(st.query(0..1, ..), None);
+        assert_eq!(SegTree2D::new(0, 0, |a: i32, b: i32| a.max(b)).query(.., ..), None);
+    }
+}
@@ -0,0 +1,322 @@
+use std::ops::{Range, RangeBounds};
+
+use super::to_range;
+
+#[derive(Clone, Copy)]
+struct BeatsNode {
+    sum: i64,
+    max1: i64,
+    max2: i64,
+    max_cnt: i64,
+    min1: i64,
+    min2: i64,
+    min_cnt: i64,
+    lazy: i64,
+}
+
+impl BeatsNode {
+    fn leaf(val: i64) -> Self {
+        Self {
+            sum: val,
+            max1: val,
+            max2: i64::MIN,
+            max_cnt: 1,
+            min1: val,
+            min2: i64::MAX,
+            min_cnt: 1,
+            lazy: 0,
+        }
+    }
+
+    fn merge(a: &BeatsNode, b: &BeatsNode) -> Self {
+        let (max1, max2, max_cnt) = match a.max1.cmp(&b.max1) {
+            std::cmp::Ordering::Equal => (a.max1, a.max2.max(b.max2), a.max_cnt + b.max_cnt),
+            std::cmp::Ordering::Greater => (a.max1, a.max2.max(b.max1), a.max_cnt),
+            std::cmp::Ordering::Less => (b.max1, a.max1.max(b.max2), b.max_cnt),
+        };
+        let (min1, min2, min_cnt) = match a.min1.cmp(&b.min1) {
+            std::cmp::Ordering::Equal => (a.min1, a.min2.min(b.min2), a.min_cnt + b.min_cnt),
+            std::cmp::Ordering::Less => (a.min1, a.min2.min(b.min1), a.min_cnt),
+            std::cmp::Ordering::Greater => (b.min1, a.min1.min(b.min2), b.min_cnt),
+        };
+        Self {
+            sum: a.sum + b.sum,
+            max1,
+            max2,
+            max_cnt,
+            min1,
+            min2,
+            min_cnt,
+            lazy: 0,
+        }
+    }
+
+    fn apply_add(&mut self, val: i64, len: usize) {
+        self.sum += val * len as i64;
+        self.max1 += val;
+        if self.max2 != i64::MIN {
+            self.max2 += val;
+        }
+        self.min1 += val;
+        if self.min2 != i64::MAX {
+            self.min2 += val;
+        }
+        self.lazy += val;
+    }
+
+    /// Only valid when `max2 < val`, so that just the maximums change
+    fn apply_chmin(&mut self, val: i64) {
+        if self.max1 <= val {
+            return;
+        }
+        self.sum -= (self.max1 - val) * self.max_cnt;
+        if self.min1 == self.max1 {
+            self.min1 = val;
+        } else if self.min2 == self.max1 {
+            self.min2 = val;
+        }
+        self.max1 = val;
+    }
+
+    /// Only valid when `min2 > val`, so that just the minimums change
+    fn apply_chmax(&mut self, val: i64) {
+        if self.min1 >= val {
+            return;
+        }
+        self.sum += (val - self.min1) * self.min_cnt;
+        if self.max1 == self.min1 {
+            self.max1 = val;
+        } else if self.max2 == self.min1 {
+            self.max2 = val;
+        }
+        self.min1 = val;
+    }
+}
+
+/// Segment tree beats (Ji driver) supporting range chmin, chmax and add with sum, min and max queries
+pub struct SegTreeBeats {
+    n: usize,
+    data: Vec<BeatsNode>,
+}
+
+impl SegTreeBeats {
+    pub fn new(n: usize) -> Self {
+        Self::new_from_iter(vec![0; n])
+    }
+
+    pub fn new_from_iter<I: IntoIterator<Item=i64>>(iter: I) -> Self {
+        let init_data = iter.into_iter().collect::<Vec<_>>();
+        let mut result = Self {
+            n: init_data.len(),
+            data: vec![BeatsNode::leaf(0); init_data.len()*4],
+        };
+        if result.n > 0 {
+            result.build(&init_data, 1, 0, init_data.len());
+        }
+        result
+    }
+
+    fn build(&mut self, init_data: &[i64], ti: usize, tl: usize, tr: usize) {
+        if tl+1 == tr {
+            self.data[ti] = BeatsNode::leaf(init_data[tl]);
+            return;
+        }
+        let tm = (tl+tr)/2;
+        self.build(init_data, ti*2, tl, tm);
+        self.build(init_data, ti*2+1, tm, tr);
+        self.pull(ti);
+    }
+
+    fn pull(&mut self, ti: usize) {
+        self.data[ti] = BeatsNode::merge(&self.data[ti*2], &self.data[ti*2+1]);
+    }
+
+    fn push(&mut self, ti: usize, tl: usize, tr: usize) {
+        let tm = (tl+tr)/2;
+        let node = self.data[ti];
+        for (ci, len) in [(ti*2, tm-tl), (ti*2+1, tr-tm)] {
+            if node.lazy != 0 {
+                self.data[ci].apply_add(node.lazy, len);
+            }
+            self.data[ci].apply_chmin(node.max1);
+            self.data[ci].apply_chmax(node.min1);
+        }
+        self.data[ti].lazy = 0;
+    }
+
+    fn to_range(&self, range_bounds: impl RangeBounds<usize>) -> Range<usize> {
+        to_range(range_bounds, self.n)
+    }
+
+    fn chmin_inner(&mut self, l: usize, r: usize, val: i64, ti: usize, tl: usize, tr: usize) {
+        if l >= r || self.data[ti].max1 <= val {
+            return;
+        }
+        if tl == l && tr == r && self.data[ti].max2 < val {
+            self.data[ti].apply_chmin(val);
+            return;
+        }
+        self.push(ti, tl, tr);
+        let tm = (tl+tr)/2;
+        self.chmin_inner(l, r.min(tm), val, ti*2, tl, tm);
+        self.chmin_inner(l.max(tm), r, val, ti*2+1, tm, tr);
+        self.pull(ti);
+    }
+
+    /// Sets every element in the range to `min(a[i], val)`
+    pub fn chmin(&mut self, range_bounds: impl RangeBounds<usize>, val: i64) {
+        let range = self.to_range(range_bounds);
+        self.chmin_inner(range.start, range.end, val, 1, 0, self.n);
+    }
+
+    fn chmax_inner(&mut self, l: usize, r: usize, val: i64, ti: usize, tl: usize, tr: usize) {
+        if l >= r || self.data[ti].min1 >= val {
+            return;
+        }
+        if tl == l && tr == r && self.data[ti].min2 > val {
+            self.data[ti].apply_chmax(val);
+            return;
+        }
+        self.push(ti, tl, tr);
+        let tm = (tl+tr)/2;
+        self.chmax_inner(l, r.min(tm), val, ti*2, tl, tm);
+        self.chmax_inner(l.max(tm), r, val, ti*2+1, tm, tr);
+        self.pull(ti);
+    }
+
+    /// Sets every element in the range to `max(a[i], val)`
+    pub fn chmax(&mut self, range_bounds: impl RangeBounds<usize>, val: i64) {
+        let range = self.to_range(range_bounds);
+        self.chmax_inner(range.start, range.end, val, 1, 0, self.n);
+    }
+
+    fn add_inner(&mut self, l: usize, r: usize, val: i64, ti: usize, tl: usize, tr: usize) {
+        if l >= r {
+            return;
+        }
+        if tl == l && tr == r {
+            self.data[ti].apply_add(val, tr-tl);
+            return;
+        }
+        self.push(ti, tl, tr);
+        let tm = (tl+tr)/2;
+        self.add_inner(l, r.min(tm), val, ti*2, tl, tm);
+        self.add_inner(l.max(tm), r, val, ti*2+1, tm, tr);
+        self.pull(ti);
+    }
+
+    /// Adds `val` to every element in the range
+    pub fn add(&mut self, range_bounds: impl RangeBounds<usize>, val: i64) {
+        let range = self.to_range(range_bounds);
+        self.add_inner(range.start, range.end, val, 1, 0, self.n);
+    }
+
+    fn query_inner(&mut self, l: usize, r: usize, ti: usize, tl: usize, tr: usize) -> Option<BeatsNode> {
+        if l >= r {
+            return None;
+        }
+        if tl == l && tr == r {
+            return Some(self.data[ti]);
+        }
+        self.push(ti, tl, tr);
+        let tm = (tl+tr)/2;
+        let left_res = self.query_inner(l, r.min(tm), ti*2, tl, tm);
+        let right_res = self.query_inner(l.max(tm), r, ti*2+1, tm, tr);
+        match (left_res, right_res) {
+            (Some(a), Some(b)) => Some(BeatsNode::merge(&a, &b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn query(&mut self, range_bounds: impl RangeBounds<usize>) -> Option<BeatsNode> {
+        let range = self.to_range(range_bounds);
+        self.query_inner(range.start, range.end, 1, 0, self.n)
+    }
+
+    /// Sum of the range, 0 for an empty range
+    pub fn sum(&mut self, range_bounds: impl RangeBounds<usize>) -> i64 {
+        self.query(range_bounds).map_or(0, |node| node.sum)
+    }
+
+    /// Minimum of the range, `None` for an empty range
+    pub fn min(&mut self, range_bounds: impl RangeBounds<usize>) -> Option<i64> {
+        self.query(range_bounds).map(|node| node.min1)
+    }
+
+    /// Maximum of the range, `None` for an empty range
+    pub fn max(&mut self, range_bounds: impl RangeBounds<usize>) -> Option<i64> {
+        self.query(range_bounds).map(|node| node.max1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::rand_u64;
+
+    use super::SegTreeBeats;
+
+    #[test]
+    fn simple() {
+        let mut st = SegTreeBeats::new_from_iter(vec![5, 1, 7, 3]);
+        st.chmin(.., 4);
+        assert_eq!(st.sum(..), 4+1+4+3);
+        st.chmax(1..3, 2);
+        assert_eq!(st.sum(..), 4+2+4+3);
+        st.add(2.., -5);
+        assert_eq!(st.min(..), Some(-2));
+        assert_eq!(st.max(1..=2), Some(2));
+        assert_eq!(st.sum(3..3), 0);
+        assert_eq!(st.min(3..3), None);
+
+        let mut empty = SegTreeBeats::new(0);
+        empty.chmin(.., 1);
+        empty.add(.., 1);
+        assert_eq!(empty.sum(..), 0);
+        assert_eq!(empty.max(..), None);
+    }
+
+    #[test]
+    fn upd_test_randomized() {
+        let n = 1000;
+        let mut data = (0..n).map(|_| (rand_u64() % 2001) as i64 - 1000).collect::<Vec<_>>();
+        let mut st = SegTreeBeats::new_from_iter(data.clone());
+
+        let samples_cnt = 10_000;
+        for _ in 0..samples_cnt {
+            let mut l = (rand_u64() as usize)%n;
+            let mut r = (rand_u64() as usize)%n;
+            if l > r {
+                std::mem::swap(&mut l, &mut r);
+            }
+            // keep some empty ranges
+            if !rand_u64().is_multiple_of(10) {
+                r += 1;
+            }
+            let val = (rand_u64() % 2001) as i64 - 1000;
+
+            match rand_u64() % 6 {
+                0 => {
+                    st.chmin(l..r, val);
+                    for x in &mut data[l..r] {
+                        *x = (*x).min(val);
+                    }
+                }
+                1 => {
+                    st.chmax(l..r, val);
+                    for x in &mut data[l..r] {
+                        *x = (*x).max(val);
+                    }
+                }
+                2 => {
+                    st.add(l..r, val);
+                    for x in &mut data[l..r] {
+                        *x += val;
+                    }
+                }
+                3 => assert_eq!(st.sum(l..r), data[l..r].iter().sum::<i64>()),
+                4 => assert_eq!(st.min(l..r), data[l..r].iter().min().copied()),
+                _ => assert_eq!(st.max(l..r), data[l..r].iter().max().copied()),
+            }
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use std::{mem, ops::{Range, RangeBounds}};
+
+use super::super::random::rand_u64;
+use super::to_range;
+
+#[derive(Default)]
+pub enum SeqTreapNodeCont<D, L> {
+    Some(Box<SeqTreapNode<D, L>>),
+    #[default]
+    Empty,
+}
+
+impl<D, L> SeqTreapNodeCont<D, L> {
+    fn len(&self) -> usize {
+        if let SeqTreapNodeCont::Some(root) = self {
+            root.len
+        } else {
+            0
+        }
+    }
+}
+
+pub struct SeqTreapNode<D, L> {
+    val: D,
+    agg: D,
+    // aggregate of the subtree in reversed order
+    rev_agg: D,
+    lazy: Option<L>,
+    reversed: bool,
+    left: SeqTreapNodeCont<D, L>,
+    right: SeqTreapNodeCont<D, L>,
+    priority: u64,
+    len: usize,
+}
+
+impl<D: Clone, L> SeqTreapNode<D, L> {
+    fn from_val(val: D) -> Self {
+        Self {
+            agg: val.clone(),
+            rev_agg: val.clone(),
+            val,
+            lazy: None,
+            reversed: false,
+            left: SeqTreapNodeCont::Empty,
+            right: SeqTreapNodeCont::Empty,
+            priority: rand_u64(),
+            len: 1,
+        }
+    }
+}
+
+/// Implicit treap, a sequence indexed by position supporting insertion, removal, cut and paste, range reverse,
+/// range aggregate with `m` and lazy range updates where `ml` combines two updates and `u` applies one to an aggregate.
+pub struct SeqTreap<D: Clone, L: Clone, M: Fn(D, D) -> D, ML: Fn(L, L) -> L, U: Fn(D, L) -> D> {
+    root: SeqTreapNodeCont<D, L>,
+    m: M,
+    ml: ML,
+    u: U,
+}
+
+impl<D: Clone, L: Clone, M: Fn(D, D) -> D, ML: Fn(L, L) -> L, U: Fn(D, L) -> D> SeqTreap<D, L, M, ML, U> {
+    pub fn new(m: M, ml: ML, u: U) -> Self {
+        Self {
+            root: SeqTreapNodeCont::Empty,
+            m,
+            ml,
+            u,
+        }
+    }
+
+    pub fn from_iter<I: IntoIterator<Item=D>>(iter: I, m: M, ml: ML, u: U) -> Self {
+        let mut result = Self::new(m, ml, u);
+        for val in iter {
+            result.push_back(val);
+        }
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn to_range(&self, range_bounds: impl RangeBounds<usize>) -> Range<usize> {
+        to_range(range_bounds, self.len())
+    }
+
+    fn apply(&self, node: &mut SeqTreapNode<D, L>, lazy: L) {
+        node.val = (self.u)(node.val.clone(), lazy.clone());
+        node.agg = (self.u)(node.agg.clone(), lazy.clone());
+        node.rev_agg = (self.u)(node.rev_agg.clone(), lazy.clone());
+        node.lazy = Some(match node.lazy.take() {
+            Some(old) => (self.ml)(old, lazy),
+            None => lazy,
+        });
+    }
+
+    /// Reverses the subtree, the children are swapped lazily in `push`
+    fn toggle(node: &mut SeqTreapNode<D, L>) {
+        node.reversed = !node.reversed;
+        mem::swap(&mut node.agg, &mut node.rev_agg);
+    }
+
+    fn push(&self, node: &mut SeqTreapNode<D, L>) {
+        if node.reversed {
+            mem::swap(&mut node.left, &mut node.right);
+            for child in [&mut node.left, &mut node.right] {
+                if let SeqTreapNodeCont::Some(child) = child {
+                    Self::toggle(child);
+                }
+            }
+            node.reversed = false;
+        }
+        if let Some(lazy) = node.lazy.take() {
+            for child in [&mut node.left, &mut node.right] {
+                if let SeqTreapNodeCont::Some(child) = child {
+                    self.apply(child, lazy.clone());
+                }
+            }
+        }
+    }
+
+    fn recompute(&self, node: &mut SeqTreapNode<D, L>) {
+        node.len = node.left.len() + node.right.len() + 1;
+        let mut agg = node.val.clone();
+        let mut rev_agg = node.val.clone();
+        if let SeqTreapNodeCont::Some(left) = &node.left {
+            agg = (self.m)(left.agg.clone(), agg);
+            rev_agg = (self.m)(rev_agg, left.rev_agg.clone());
+        }
+        if let SeqTreapNodeCont::Some(right) = &node.right {
+            agg = (self.m)(agg, right.agg.clone());
+            rev_agg = (self.m)(right.rev_agg.clone(), rev_agg);
+        }
+        node.agg = agg;
+        node.rev_agg = rev_agg;
+    }
+
+    /// The first returned part contains the first `k` elements
+    fn split_node(&self, cont: SeqTreapNodeCont<D, L>, k: usize) -> (SeqTreapNodeCont<D, L>, SeqTreapNodeCont<D, L>) {
+        let SeqTreapNodeCont::Some(mut root) = cont else {
+            return (SeqTreapNodeCont::Empty, SeqTreapNodeCont::Empty);
+        };
+        self.push(&mut root);
+
+        let left_len = root.left.len();
+        if k <= left_len {
+            let (left_res, left_rest) = self.split_node(mem::take(&mut root.left), k);
+            root.left = left_rest;
+            self.recompute(&mut root);
+            (left_res, SeqTreapNodeCont::Some(root))
+        } else {
+            let (right_rest, right_res) = self.split_node(mem::take(&mut root.right), k - left_len - 1);
+            root.right = right_rest;
+            self.recompute(&mut root);
+            (SeqTreapNodeCont::Some(root), right_res)
+        }
+    }
+
+    fn merge_node(&self, left: SeqTreapNodeCont<D, L>, right: SeqTreapNodeCont<D, L>) -> SeqTreapNodeCont<D, L> {
+        let SeqTreapNodeCont::Some(mut left_root) = left else {
+            return right;
+        };
+        let SeqTreapNodeCont::Some(mut right_root) = right else {
+            return SeqTreapNodeCont::Some(left_root);
+        };
+        if left_root.priority >= right_root.priority {
+            self.push(&mut left_root);
+            let right_child = mem::take(&mut left_root.right);
+            left_root.right = self.merge_node(right_child, SeqTreapNodeCont::Some(right_root));
+            self.recompute(&mut left_root);
+            SeqTreapNodeCont::Some(left_root)
+        } else {
+            self.push(&mut right_root);
+            let left_child = mem::take(&mut right_root.left);
+            right_root.left = self.merge_node(SeqTreapNodeCont::Some(left_root), left_child);
+            self.recompute(&mut right_root);
+            SeqTreapNodeCont::Some(right_root)
+        }
+    }
+
+    /// Runs `f` on the subtree containing exactly the elements in `range`
+    fn with_range<R>(&mut self, range_bounds: impl RangeBounds<usize>, f: impl FnOnce(&Self, &mut SeqTreapNodeCont<D, L>) -> R) -> R {
+        let range = self.to_range(range_bounds);
+        assert!(range.start <= range.end && range.end <= self.len());
+        let root = mem::take(&mut self.root);
+        let (left, rest) = self.split_node(root, range.start);
+        let (mut mid, right) = self.split_node(rest, range.end - range.start);
+        let result = f(self, &mut mid);
+        let rest = self.merge_node(mid, right);
+        self.root = self.merge_node(left, rest);
+        result
+    }
+
+    pub fn insert(&mut self, i: usize, val: D) {
+        assert!(i <= self.len());
+        let root = mem::take(&mut self.root);
+        let (left, right) = self.split_node(root, i);
+        let node = SeqTreapNodeCont::Some(Box::new(SeqTreapNode::from_val(val)));
+        let left = self.merge_node(left, node);
+        self.root = self.merge_node(left, right);
+    }
+
+    pub fn push_back(&mut self, val: D) {
+        self.insert(self.len(), val);
+    }
+
+    pub fn remove(&mut self, i: usize) -> D {
+        assert!(i < self.len());
+        let root = mem::take(&mut self.root);
+        let (left, rest) = self.split_node(root, i);
+        let (mid, right) = self.split_node(rest, 1);
+        self.root = self.merge_node(left, right);
+        let SeqTreapNodeCont::Some(node) = mid else {
+            unreachable!();
+        };
+        node.val
+    }
+
+    pub fn get(&mut self, i: usize) -> D {
+        self.query(i..=i)
+    }
+
+    pub fn set(&mut self, i: usize, val: D) {
+        self.with_range(i..=i, |_, mid| {
+            if let SeqTreapNodeCont::Some(node) = mid {
+                node.agg = val.clone();
+                node.rev_agg = val.clone();
+                node.val = val;
+            }
+        });
+    }
+
+    /// Aggregate of the elements in the range, which must be non-empty
+    pub fn query(&mut self, range_bounds: impl RangeBounds<usize>) -> D {
+        self.with_range(range_bounds, |_, mid| {
+            let SeqTreapNodeCont::Some(node) = mid else {
+                panic!("query on an empty range");
+            };
+            node.agg.clone()
+        })
+    }
+
+    /// Applies `val` to every element in the range
+    pub fn update(&mut self, range_bounds: impl RangeBounds<usize>, val: L) {
+        self.with_range(range_bounds, |treap, mid| {
+            if let SeqTreapNodeCont::Some(node) = mid {
+                treap.apply(node, val);
+            }
+        });
+    }
+
+    /// Reverses the order of the elements in the range
+    pub fn reverse(&mut self, range_bounds: impl RangeBounds<usize>) {
+        self.with_range(range_bounds, |_, mid| {
+            if let SeqTreapNodeCont::Some(node) = mid {
+                Self::toggle(node);
+            }
+        });
+    }
+
+    /// Returns all the elements in order
+    pub fn to_vec(&mut self) -> Vec<D> {
+        fn collect<D: Clone, L: Clone, M: Fn(D, D) -> D, ML: Fn(L, L) -> L, U: Fn(D, L) -> D>(
+            treap: &SeqTreap<D, L, M, ML, U>,
+            cont: &mut SeqTreapNodeCont<D, L>,
+            result: &mut Vec<D>,
+        ) {
+            if let SeqTreapNodeCont::Some(node) = cont {
+                treap.push(node);
+                collect(treap, &mut node.left, result);
+                result.push(node.val.clone());
+                collect(treap, &mut node.right, result);
+            }
+        }
+        let mut result = Vec::with_capacity(self.len());
+        let mut root = mem::take(&mut self.root);
+        collect(self, &mut root, &mut result);
+        self.root = root;
+        result
+    }
+}
+
+impl<D: Clone, L: Clone, M: Fn(D, D) -> D + Clone, ML: Fn(L, L) -> L + Clone, U: Fn(D, L) -> D + Clone> SeqTreap<D, L, M, ML, U> {
+    /// Returns two sequences, the first one with the first `k` elements and the second one with the rest
+    pub fn split(mut self, k: usize) -> (Self, Self) {
+        let root = mem::take(&mut self.root);
+        let (left, right) = self.split_node(root, k);
+        (
+            Self { root: left, m: self.m.clone(), ml: self.ml.clone(), u: self.u.clone() },
+            Self { root: right, m: self.m, ml: self.ml, u: self.u },
+        )
+    }
+}
+
+impl<D: Clone, L: Clone, M: Fn(D, D) -> D, ML: Fn(L, L) -> L, U: Fn(D, L) -> D> SeqTreap<D, L, M, ML, U> {
+    /// Appends all the elements of `other` after the elements of `self`
+    pub fn merge(mut self, other: Self) -> Self {
+        let root = mem::take(&mut self.root);
+        self.root = self.merge_node(root, other.root);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::XorShift;
+
+    use super::*;
+
+    #[test]
+    fn test_insert_remove_reverse() {
+        let mut treap = SeqTreap::from_iter(1..=5, |a, b| a+b, |_: i32, l| l, |d, _| d);
+        treap.insert(2, 10);
+        assert_eq!(treap.to_vec(), vec![1, 2, 10, 3, 4, 5]);
+        treap.reverse(1..5);
+        assert_eq!(treap.to_vec(), vec![1, 4, 3, 10, 2, 5]);
+        assert_eq!(treap.remove(3), 10);
+        assert_eq!(treap.to_vec(), vec![1, 4, 3, 2, 5]);
+        assert_eq!(treap.query(1..=3), 9);
+        assert_eq!(treap.get(4), 5);
+        assert_eq!(treap.len(), 5);
+    }
+
+    #[test]
+    fn test_split_merge() {
+        let treap = SeqTreap::from_iter(0..10, |a, b| a+b, |_: i32, l| l, |d, _| d);
+        let (left, right) = treap.split(4);
+        assert_eq!(left.len(), 4);
+        let (mut mid, right) = right.split(3);
+        assert_eq!(mid.to_vec(), vec![4, 5, 6]);
+        // cut [4, 7) and paste it at the front
+        let mut treap = mid.merge(left).merge(right);
+        assert_eq!(treap.to_vec(), vec![4, 5, 6, 0, 1, 2, 3, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_brute() {
+        let mut rand = XorShift::new(10);
+        let mut data: Vec<i64> = vec![];
+        // (sum, len) with range add
+        let mut treap = SeqTreap::new(|a: (i64, i64), b: (i64, i64)| (a.0+b.0, a.1+b.1), |l1: i64, l2| l1+l2, |d, l| (d.0+l*d.1, d.1));
+        for _ in 0..3000 {
+            let op = rand.next()%6;
+            if data.is_empty() || op == 0 {
+                let i = rand.next() as usize % (data.len()+1);
+                let val = (rand.next()%100) as i64;
+                treap.insert(i, (val, 1));
+                data.insert(i, val);
+                continue;
+            }
+            let mut l = rand.next() as usize % data.len();
+            let mut r = rand.next() as usize % data.len();
+            if l > r {
+                std::mem::swap(&mut l, &mut r);
+            }
+            match op {
+                1 => assert_eq!(treap.remove(l).0, data.remove(l)),
+                2 => {
+                    treap.reverse(l..=r);
+                    data[l..=r].reverse();
+                }
+                3 => {
+                    let val = (rand.next()%100) as i64 - 50;
+                    treap.update(l..=r, val);
+                    for x in &mut data[l..=r] {
+                        *x += val;
+                    }
+                }
+                4 => assert_eq!(treap.query(l..=r).0, data[l..=r].iter().sum::<i64>()),
+                _ => assert_eq!(treap.to_vec().into_iter().map(|d| d.0).collect::<Vec<_>>(), data),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reverse_non_commutative() {
+        // polynomial hash (hash, base^len), the order of the elements matters
+        fn hash(a: (u64, u64), b: (u64, u64)) -> (u64, u64) {
+            (a.0.wrapping_mul(b.1).wrapping_add(b.0), a.1.wrapping_mul(b.1))
+        }
+        let mut rand = XorShift::new(11);
+        let n = 200;
+        let mut data = (0..n).map(|_| rand.next()%26).collect::<Vec<_>>();
+        let mut treap = SeqTreap::from_iter(data.iter().map(|&c| (c, 131)), hash, |_: (), _| (), |d, _| d);
+        for _ in 0..2000 {
+            let mut l = rand.next() as usize % n;
+            let mut r = rand.next() as usize % n;
+            if l > r {
+                std::mem::swap(&mut l, &mut r);
+            }
+            if rand.next().is_multiple_of(2) {
+                treap.reverse(l..=r);
+                data[l..=r].reverse();
+            }
+            else {
+                let expected = data[l..=r].iter().map(|&c| (c, 131)).reduce(hash).unwrap();
+                assert_eq!(treap.query(l..=r), expected);
+            }
+        }
+        assert_eq!(treap.to_vec().into_iter().map(|d| d.0).collect::<Vec<_>>(), data);
+    }
+}
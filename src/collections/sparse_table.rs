@@ -1,5 +1,8 @@
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
 
+use super::to_range;
+
+/// Only correct for idempotent functions like min/max, use `DisjointSparseTable` for sums, products and other associative functions
 pub struct SparseTable<T: Clone, F: Fn(T, T) -> T> {
     data: Vec<Vec<T>>,
     f: F 
@@ -8,8 +11,7 @@ pub struct SparseTable<T: Clone, F: Fn(T, T) -> T> {
 impl<T: Clone, F: Fn(T, T) -> T> SparseTable<T, F> {
     pub fn new_from_iter(iter: impl IntoIterator<Item=T>,f: F) -> Self {
         let data_init = iter.into_iter().collect::<Vec<_>>();
-        assert!(data_init.len() != 0);
-        let lg = (data_init.len().ilog2()+1) as usize;
+        let lg = data_init.len().checked_ilog2().map_or(0, |lg| lg as usize + 1);
         let mut data = data_init.into_iter().map(|val| vec![val; lg]).collect::<Vec<_>>();
         for i in 1..lg {
             let offs = 1<<(i-1);
@@ -22,47 +24,306 @@ impl<T: Clone, F: Fn(T, T) -> T> SparseTable<T, F> {
             f
         }
     }
-    pub fn query(&self, range: impl RangeBounds<usize>) -> T {
-        let l = match range.start_bound() {
-            std::ops::Bound::Included(s) => *s,
-            std::ops::Bound::Excluded(s) => *s+1,
-            std::ops::Bound::Unbounded => 0,
-        };
-        let r = match range.end_bound() {
-            std::ops::Bound::Included(e) => *e,
-            std::ops::Bound::Excluded(e) => *e-1,
-            std::ops::Bound::Unbounded => self.data.len()-1,
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    /// Returns `None` for an empty range
+    pub fn query(&self, range: impl RangeBounds<usize>) -> Option<T> {
+        let Range { start: l, end: r } = to_range(range, self.data.len());
+        if l >= r {
+            return None;
+        }
+
+        let i = (r-l).ilog2() as usize;
+
+        Some((self.f)(self.data[l][i].clone(), self.data[r-(1<<i)][i].clone()))
+    }
+}
+
+/// Sparse table returning the index of the minimum (or maximum) instead of its value, the leftmost one on ties
+pub struct ArgSparseTable<T: Ord> {
+    values: Vec<T>,
+    data: Vec<Vec<usize>>,
+    max: bool,
+}
+
+impl<T: Ord> ArgSparseTable<T> {
+    pub fn new_argmin(iter: impl IntoIterator<Item=T>) -> Self {
+        Self::new(iter, false)
+    }
+    pub fn new_argmax(iter: impl IntoIterator<Item=T>) -> Self {
+        Self::new(iter, true)
+    }
+    fn new(iter: impl IntoIterator<Item=T>, max: bool) -> Self {
+        let values = iter.into_iter().collect::<Vec<_>>();
+        let lg = values.len().checked_ilog2().map_or(0, |lg| lg as usize + 1);
+        let mut result = Self {
+            data: vec![(0..values.len()).collect()],
+            values,
+            max,
         };
-        if r-l <= 1 {
-            return (self.f)(self.data[l][0].clone(), self.data[r][0].clone());
+        for i in 1..lg {
+            let offs = 1<<(i-1);
+            let level = (0..=result.values.len()-2*offs)
+                .map(|j| result.better(result.data[i-1][j], result.data[i-1][j+offs]))
+                .collect::<Vec<_>>();
+            result.data.push(level);
         }
+        result
+    }
+    /// Of two indices `a < b` returns the one with the better value, `a` on ties
+    fn better(&self, a: usize, b: usize) -> usize {
+        let b_better = if self.max { self.values[b] > self.values[a] } else { self.values[b] < self.values[a] };
+        if b_better { b } else { a }
+    }
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    pub fn value(&self, i: usize) -> &T {
+        &self.values[i]
+    }
+    /// Returns the index of the minimum (or maximum) in the range, `None` for an empty range
+    pub fn query(&self, range: impl RangeBounds<usize>) -> Option<usize> {
+        let Range { start: l, end: r } = to_range(range, self.values.len());
+        if l >= r {
+            return None;
+        }
+        let i = (r-l).ilog2() as usize;
+        Some(self.better(self.data[i][l], self.data[i][r-(1<<i)]))
+    }
+}
+
+/// Range minimum query with O(n) preprocessing and O(1) query, using blocks of 64 elements
+/// with a bitmask of the monotonic stack inside each block and a sparse table over the blocks.
+/// For maximum use `std::cmp::Reverse`.
+pub struct LinearRmq<T: Ord + Clone> {
+    values: Vec<T>,
+    // bit j of masks[i] is set if i's block start + j is on the monotonic stack after processing i
+    masks: Vec<u64>,
+    blocks: ArgSparseTable<T>,
+    block_arg: Vec<usize>,
+}
+
+impl<T: Ord + Clone> LinearRmq<T> {
+    const BLOCK: usize = 64;
 
-        let dist = r-l;
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>) -> Self {
+        let values = iter.into_iter().collect::<Vec<_>>();
+        let mut masks = vec![0u64; values.len()];
+        let mut block_arg = vec![];
+        for start in (0..values.len()).step_by(Self::BLOCK) {
+            let mut mask = 0u64;
+            for i in start..(start+Self::BLOCK).min(values.len()) {
+                while mask != 0 {
+                    let top = start + 63 - mask.leading_zeros() as usize;
+                    if values[top] <= values[i] {
+                        break;
+                    }
+                    mask ^= 1 << (top-start);
+                }
+                mask |= 1 << (i-start);
+                masks[i] = mask;
+            }
+            block_arg.push(start + mask.trailing_zeros() as usize);
+        }
+        Self {
+            blocks: ArgSparseTable::new_argmin(block_arg.iter().map(|&i| values[i].clone())),
+            block_arg,
+            values,
+            masks,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    /// Index of the minimum in `[l, r]` which must be within a single block
+    fn in_block(&self, l: usize, r: usize) -> usize {
+        let start = l / Self::BLOCK * Self::BLOCK;
+        let mask = self.masks[r] & (u64::MAX << (l-start));
+        start + mask.trailing_zeros() as usize
+    }
+    /// Returns the index of the minimum in the range, the leftmost one on ties, `None` for an empty range
+    pub fn argmin(&self, range: impl RangeBounds<usize>) -> Option<usize> {
+        let Range { start: l, end: r } = to_range(range, self.values.len());
+        if l >= r {
+            return None;
+        }
+        let r = r-1;
+        let (bl, br) = (l / Self::BLOCK, r / Self::BLOCK);
+        if bl == br {
+            return Some(self.in_block(l, r));
+        }
+        let mut result = self.in_block(l, (bl+1)*Self::BLOCK-1);
+        if let Some(block) = self.blocks.query(bl+1..br) {
+            let i = self.block_arg[block];
+            if self.values[i] < self.values[result] {
+                result = i;
+            }
+        }
+        let i = self.in_block(br*Self::BLOCK, r);
+        if self.values[i] < self.values[result] {
+            result = i;
+        }
+        Some(result)
+    }
+    /// Returns the minimum in the range, `None` for an empty range
+    pub fn query(&self, range: impl RangeBounds<usize>) -> Option<T> {
+        self.argmin(range).map(|i| self.values[i].clone())
+    }
+}
 
-        let i = dist.ilog2() as usize;
+/// Sparse table answering queries in O(1) for any associative function, not only idempotent ones
+pub struct DisjointSparseTable<T: Clone, F: Fn(T, T) -> T> {
+    // data[h][i] = f over [i, mid) or [mid, i] where mid is the middle of i's block of size 2^h
+    data: Vec<Vec<T>>,
+    f: F
+}
 
-        (self.f)(self.data[l][i].clone(), self.data[r-(1<<i)+1][i].clone())
+impl<T: Clone, F: Fn(T, T) -> T> DisjointSparseTable<T, F> {
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>, f: F) -> Self {
+        let data_init = iter.into_iter().collect::<Vec<_>>();
+        let n = data_init.len();
+        let lg = n.checked_ilog2().map_or(0, |lg| lg as usize + 1);
+        let mut data = vec![data_init; lg+1];
+        for h in 1..=lg {
+            let half = 1<<(h-1);
+            for start in (0..n).step_by(1<<h) {
+                let mid = start+half;
+                if mid >= n {
+                    break;
+                }
+                for i in (start..mid-1).rev() {
+                    data[h][i] = (f)(data[0][i].clone(), data[h][i+1].clone());
+                }
+                for i in mid+1..(start+(1<<h)).min(n) {
+                    data[h][i] = (f)(data[h][i-1].clone(), data[0][i].clone());
+                }
+            }
+        }
+        Self {
+            data,
+            f
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.data[0].len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.data[0].is_empty()
+    }
+    /// Returns `None` for an empty range
+    pub fn query(&self, range: impl RangeBounds<usize>) -> Option<T> {
+        let Range { start: l, end: r } = to_range(range, self.data[0].len());
+        if l >= r {
+            return None;
+        }
+        let r = r-1;
+        if l == r {
+            return Some(self.data[0][l].clone());
+        }
+        let h = ((l^r).ilog2()+1) as usize;
+        Some((self.f)(self.data[h][l].clone(), self.data[h][r].clone()))
     }
 }
 
 
+/// Sparse table over a 2D grid for idempotent functions like min/max, answering rectangle queries in O(1)
+pub struct SparseTable2D<T: Clone, F: Fn(T, T) -> T> {
+    // data[kr][kc][i][j] = f over rows [i, i+2^kr) and columns [j, j+2^kc)
+    data: Vec<Vec<Vec<Vec<T>>>>,
+    n: usize,
+    m: usize,
+    f: F
+}
+
+impl<T: Clone, F: Fn(T, T) -> T> SparseTable2D<T, F> {
+    pub fn new_from_iter<R: IntoIterator<Item=T>>(iter: impl IntoIterator<Item=R>, f: F) -> Self {
+        let grid = iter.into_iter().map(|row| row.into_iter().collect::<Vec<_>>()).collect::<Vec<_>>();
+        let (n, m) = (grid.len(), grid.first().map_or(0, |row| row.len()));
+        assert!(grid.iter().all(|row| row.len() == m));
+        if n == 0 || m == 0 {
+            return Self {
+                data: vec![],
+                n,
+                m,
+                f
+            };
+        }
+        let (lg_n, lg_m) = ((n.ilog2()+1) as usize, (m.ilog2()+1) as usize);
+
+        let mut data: Vec<Vec<Vec<Vec<T>>>> = vec![vec![grid]];
+        for kc in 1..lg_m {
+            let offs = 1<<(kc-1);
+            let prev = &data[0][kc-1];
+            let level = prev.iter().map(|row| {
+                (0..row.len()-offs).map(|j| (f)(row[j].clone(), row[j+offs].clone())).collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+            data[0].push(level);
+        }
+        for kr in 1..lg_n {
+            let offs = 1<<(kr-1);
+            let levels = (0..lg_m).map(|kc| {
+                let prev = &data[kr-1][kc];
+                (0..prev.len()-offs).map(|i| {
+                    prev[i].iter().zip(prev[i+offs].iter()).map(|(a, b)| (f)(a.clone(), b.clone())).collect::<Vec<_>>()
+                }).collect::<Vec<_>>()
+            }).collect::<Vec<_>>();
+            data.push(levels);
+        }
+        Self {
+            data,
+            n,
+            m,
+            f
+        }
+    }
+
+    /// Inclusive ends of the range, `None` if it is empty
+    fn to_inclusive(range: impl RangeBounds<usize>, len: usize) -> Option<(usize, usize)> {
+        let Range { start, end } = to_range(range, len);
+        (start < end).then(|| (start, end-1))
+    }
+
+    /// Returns `None` if `rows` or `cols` is empty
+    pub fn query(&self, rows: impl RangeBounds<usize>, cols: impl RangeBounds<usize>) -> Option<T> {
+        let (r1, r2) = Self::to_inclusive(rows, self.n)?;
+        let (c1, c2) = Self::to_inclusive(cols, self.m)?;
+        let kr = (r2-r1+1).ilog2() as usize;
+        let kc = (c2-c1+1).ilog2() as usize;
+        let level = &self.data[kr][kc];
+        let (r3, c3) = (r2+1-(1<<kr), c2+1-(1<<kc));
+        let top = (self.f)(level[r1][c1].clone(), level[r1][c3].clone());
+        let bottom = (self.f)(level[r3][c1].clone(), level[r3][c3].clone());
+        Some((self.f)(top, bottom))
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use crate::random::{self};
 
-    use super::SparseTable;
+    use super::{ArgSparseTable, DisjointSparseTable, LinearRmq, SparseTable, SparseTable2D};
 
     #[test]
     fn query() {
         let sparse_table = SparseTable::new_from_iter(vec![0, 2, 1, 5], |a, b| a.min(b));
 
-        assert!(sparse_table.query(0..=3) == 0);
-        assert!(sparse_table.query(..) == 0);
-        assert!(sparse_table.query(3..) == 5);
-        assert!(sparse_table.query(2..) == 1);
-        assert!(sparse_table.query(1..=1) == 2);
-        assert!(sparse_table.query(1..3) == 1);
+        assert!(sparse_table.query(0..=3) == Some(0));
+        assert!(sparse_table.query(..) == Some(0));
+        assert!(sparse_table.query(3..) == Some(5));
+        assert!(sparse_table.query(2..) == Some(1));
+        assert!(sparse_table.query(1..=1) == Some(2));
+        assert!(sparse_table.query(1..3) == Some(1));
     }
     #[test]
     fn query_brute()  {
@@ -73,9 +334,114 @@ mod tests {
         for i in 0..100 {
             for j in i+1..100 {
                 let mn = arr[i..=j].iter().min().unwrap();
-                assert_eq!(*mn, st.query(i..=j));
+                assert_eq!(Some(*mn), st.query(i..=j));
+            }
+        }
+    }
+
+    #[test]
+    fn query_2d_brute() {
+        let mut rand = random::XorShift::new(1);
+        let (n, m) = (13, 20);
+        let grid = (0..n).map(|_| (0..m).map(|_| rand.next()%100).collect::<Vec<_>>()).collect::<Vec<_>>();
+        let st = SparseTable2D::new_from_iter(grid.clone(), |a, b| a.max(b));
+
+        for r1 in 0..n {
+            for r2 in r1..n {
+                for c1 in 0..m {
+                    for c2 in c1..m {
+                        let mx = grid[r1..=r2].iter().map(|row| *row[c1..=c2].iter().max().unwrap()).max().unwrap();
+                        assert_eq!(Some(mx), st.query(r1..=r2, c1..=c2));
+                    }
+                }
+            }
+        }
+        assert_eq!(st.query(.., ..), grid.iter().flatten().copied().max());
+        assert_eq!(st.query(3..3, ..), None);
+        assert_eq!(st.query(.., 5..5), None);
+    }
+
+    #[test]
+    fn empty_2d() {
+        let st = SparseTable2D::new_from_iter(Vec::<Vec<i32>>::new(), |a, b| a.max(b));
+        assert_eq!(st.query(.., ..), None);
+        let st = SparseTable2D::new_from_iter(vec![Vec::<i32>::new(); 3], |a, b| a.max(b));
+        assert_eq!(st.query(1..2, ..), None);
+    }
+
+    #[test]
+    fn disjoint_query_brute() {
+        let mut rand = random::XorShift::new(2);
+        for n in 1..70 {
+            let arr = (0..n).map(|_| rand.next()%1000).collect::<Vec<_>>();
+            let st = DisjointSparseTable::new_from_iter(arr.clone(), |a, b| a+b);
+            for i in 0..n {
+                for j in i..n {
+                    assert_eq!(Some(arr[i..=j].iter().sum::<u64>()), st.query(i..=j));
+                }
             }
+            assert_eq!(Some(arr.iter().sum::<u64>()), st.query(..));
         }
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn disjoint_query_non_commutative() {
+        let words = ["a", "b", "c", "d", "e", "f", "g"].map(String::from);
+        let st = DisjointSparseTable::new_from_iter(words, |a, b| a+&b);
+        assert_eq!(st.query(..).unwrap(), "abcdefg");
+        assert_eq!(st.query(1..6).unwrap(), "bcdef");
+        assert_eq!(st.query(3..=3).unwrap(), "d");
+    }
+
+    #[test]
+    fn query_empty() {
+        let st = SparseTable::new_from_iter(Vec::<i32>::new(), |a, b| a.min(b));
+        assert!(st.is_empty());
+        assert_eq!(st.query(..), None);
+
+        let st = SparseTable::new_from_iter(vec![3, 1, 2], |a, b| a.min(b));
+        assert_eq!(st.query(0..0), None);
+        assert_eq!(st.query(3..), None);
+
+        let st = DisjointSparseTable::new_from_iter(Vec::<i32>::new(), |a, b| a+b);
+        assert_eq!(st.query(..), None);
+        assert_eq!(LinearRmq::new_from_iter(Vec::<i32>::new()).query(..), None);
+        assert_eq!(ArgSparseTable::new_argmin(Vec::<i32>::new()).query(..), None);
+    }
+
+    #[test]
+    fn arg_query_brute() {
+        let mut rand = random::XorShift::new(3);
+        let arr = (0..100).map(|_| rand.next()%10).collect::<Vec<_>>();
+        let argmin = ArgSparseTable::new_argmin(arr.clone());
+        let argmax = ArgSparseTable::new_argmax(arr.clone());
+        for i in 0..100 {
+            for j in i+1..=100 {
+                let mn = *arr[i..j].iter().min().unwrap();
+                let mx = *arr[i..j].iter().max().unwrap();
+                assert_eq!(argmin.query(i..j), Some(i + arr[i..j].iter().position(|x| *x == mn).unwrap()));
+                assert_eq!(argmax.query(i..j), Some(i + arr[i..j].iter().position(|x| *x == mx).unwrap()));
+            }
+        }
+        assert_eq!(*argmax.value(argmax.query(..).unwrap()), 9);
+    }
+
+    #[test]
+    fn linear_rmq_brute() {
+        let mut rand = random::XorShift::new(4);
+        for n in [1, 2, 63, 64, 65, 200, 1000] {
+            let arr = (0..n).map(|_| rand.next()%50).collect::<Vec<_>>();
+            let rmq = LinearRmq::new_from_iter(arr.clone());
+            for _ in 0..2000 {
+                let mut l = rand.next() as usize % n;
+                let mut r = rand.next() as usize % n;
+                if l > r {
+                    std::mem::swap(&mut l, &mut r);
+                }
+                let mn = *arr[l..=r].iter().min().unwrap();
+                assert_eq!(rmq.argmin(l..=r), Some(l + arr[l..=r].iter().position(|x| *x == mn).unwrap()));
+                assert_eq!(rmq.query(l..=r), Some(mn));
+            }
+        }
+    }
+}
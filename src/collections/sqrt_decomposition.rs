@@ -0,0 +1,229 @@
+use std::ops::{Range, RangeBounds};
+
+use super::to_range;
+
+/// Summary function keeping a sorted copy of the block, see `BlockView::count_less`
+pub fn sorted_block<T: Ord + Clone>(values: &[T]) -> Vec<T> {
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    sorted
+}
+
+/// A whole block seen by a query. `values` and `summary` don't include `lazy`, which applies on top of them
+pub struct BlockView<'a, T, L, S> {
+    pub values: &'a [T],
+    pub lazy: Option<&'a L>,
+    pub summary: &'a S,
+}
+
+impl<T: Ord, L> BlockView<'_, T, L, Vec<T>> {
+    /// Number of values less than `x` in a block summarized by `sorted_block`
+    pub fn count_less(&self, x: &T) -> usize {
+        self.summary.partition_point(|v| v < x)
+    }
+
+    /// Number of values less than or equal to `x` in a block summarized by `sorted_block`
+    pub fn count_less_eq(&self, x: &T) -> usize {
+        self.summary.partition_point(|v| v <= x)
+    }
+}
+
+/// Array split into blocks, each with a lazy tag and a summary rebuilt by `summarize` when the block changes partially.
+/// Updating a whole block only composes its tag, so `summary` never has the tag applied.
+pub struct SqrtDecomposition<T: Clone, L: Clone, S, U: Fn(&mut T, &L), C: Fn(&L, &L) -> L, B: Fn(&[T]) -> S> {
+    values: Vec<T>,
+    block_size: usize,
+    lazy: Vec<Option<L>>,
+    summaries: Vec<S>,
+    // applies a tag to a single value
+    apply: U,
+    // composes an older tag with a newer one
+    compose: C,
+    summarize: B,
+}
+
+impl<T: Clone, L: Clone, S, U: Fn(&mut T, &L), C: Fn(&L, &L) -> L, B: Fn(&[T]) -> S> SqrtDecomposition<T, L, S, U, C, B> {
+    /// `block_size` of about sqrt(n) balances whole and partial blocks, 0 picks that automatically
+    pub fn new_from_iter(iter: impl IntoIterator<Item=T>, block_size: usize, apply: U, compose: C, summarize: B) -> Self {
+        let values = iter.into_iter().collect::<Vec<_>>();
+        let block_size = if block_size == 0 { (values.len() as f64).sqrt() as usize } else { block_size }.max(1);
+        let summaries = values.chunks(block_size).map(&summarize).collect::<Vec<_>>();
+        Self {
+            lazy: vec![None; summaries.len()],
+            values,
+            block_size,
+            summaries,
+            apply,
+            compose,
+            summarize,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn block_range(&self, b: usize) -> Range<usize> {
+        b*self.block_size..((b+1)*self.block_size).min(self.values.len())
+    }
+
+    /// Applies the pending tag of block `b` to its values
+    fn push(&mut self, b: usize) {
+        if let Some(tag) = self.lazy[b].take() {
+            let range = self.block_range(b);
+            for value in &mut self.values[range] {
+                (self.apply)(value, &tag);
+            }
+        }
+    }
+
+    fn rebuild(&mut self, b: usize) {
+        self.summaries[b] = (self.summarize)(&self.values[self.block_range(b)]);
+    }
+
+    pub fn get(&self, i: usize) -> T {
+        let mut value = self.values[i].clone();
+        if let Some(tag) = &self.lazy[i / self.block_size] {
+            (self.apply)(&mut value, tag);
+        }
+        value
+    }
+
+    pub fn set(&mut self, i: usize, val: T) {
+        let b = i / self.block_size;
+        self.push(b);
+        self.values[i] = val;
+        self.rebuild(b);
+    }
+
+    /// Applies `tag` to every element of the range
+    pub fn update(&mut self, range_bounds: impl RangeBounds<usize>, tag: L) {
+        let Range { start: l, end: r } = to_range(range_bounds, self.values.len());
+        if l >= r {
+            return;
+        }
+        let (bl, br) = (l / self.block_size, (r-1) / self.block_size);
+        for b in bl..=br {
+            let block = self.block_range(b);
+            if l <= block.start && block.end <= r {
+                self.lazy[b] = Some(match &self.lazy[b] {
+                    Some(old) => (self.compose)(old, &tag),
+                    None => tag.clone(),
+                });
+            }
+            else {
+                self.push(b);
+                for i in l.max(block.start)..r.min(block.end) {
+                    (self.apply)(&mut self.values[i], &tag);
+                }
+                self.rebuild(b);
+            }
+        }
+    }
+
+    /// Folds the range left to right, calling `partial` with single elements (tag applied) of blocks only partially covered
+    /// and `full` with the blocks covered entirely
+    pub fn query<A>(
+        &self,
+        range_bounds: impl RangeBounds<usize>,
+        init: A,
+        mut partial: impl FnMut(A, T) -> A,
+        mut full: impl FnMut(A, BlockView<T, L, S>) -> A,
+    ) -> A {
+        let Range { start: l, end: r } = to_range(range_bounds, self.values.len());
+        let mut result = init;
+        if l >= r {
+            return result;
+        }
+        let (bl, br) = (l / self.block_size, (r-1) / self.block_size);
+        for b in bl..=br {
+            let block = self.block_range(b);
+            if l <= block.start && block.end <= r {
+                result = full(result, BlockView {
+                    values: &self.values[block],
+                    lazy: self.lazy[b].as_ref(),
+                    summary: &self.summaries[b],
+                });
+            }
+            else {
+                for i in l.max(block.start)..r.min(block.end) {
+                    result = partial(result, self.get(i));
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    #[test]
+    fn add_count_at_least() {
+        let mut rand = random::XorShift::new(9);
+        for block_size in [0, 1, 3, 50] {
+            let mut arr = (0..120).map(|_| (rand.next() % 100) as i64).collect::<Vec<_>>();
+            let mut sd = SqrtDecomposition::new_from_iter(arr.clone(), block_size, |v: &mut i64, t: &i64| *v += t, |a, b| a+b, sorted_block);
+            for _ in 0..1000 {
+                let l = rand.next() as usize % 121;
+                let r = l + rand.next() as usize % (121 - l);
+                match rand.next() % 3 {
+                    0 => {
+                        let x = (rand.next() % 21) as i64 - 10;
+                        sd.update(l..r, x);
+                        arr[l..r].iter_mut().for_each(|v| *v += x);
+                    }
+                    1 => {
+                        let x = (rand.next() % 150) as i64;
+                        let count = sd.query(l..r, 0,
+                            |acc, v| acc + (v >= x) as usize,
+                            |acc, block| acc + block.values.len() - block.count_less(&(x - block.lazy.copied().unwrap_or(0))),
+                        );
+                        assert_eq!(count, arr[l..r].iter().filter(|v| **v >= x).count());
+                    }
+                    _ => {
+                        let i = rand.next() as usize % 120;
+                        let v = (rand.next() % 100) as i64;
+                        sd.set(i, v);
+                        arr[i] = v;
+                    }
+                }
+            }
+            assert_eq!((0..120).map(|i| sd.get(i)).collect::<Vec<_>>(), arr);
+        }
+    }
+
+    #[test]
+    fn assign_sum() {
+        let mut rand = random::XorShift::new(10);
+        let mut arr = (0..90).map(|_| rand.next() % 100).collect::<Vec<_>>();
+        let mut sd = SqrtDecomposition::new_from_iter(arr.clone(), 0, |v: &mut u64, t: &u64| *v = *t, |_, b| *b, |b| b.iter().sum::<u64>());
+        for _ in 0..1000 {
+            let l = rand.next() as usize % 91;
+            let r = l + rand.next() as usize % (91 - l);
+            if rand.next().is_multiple_of(2) {
+                let x = rand.next() % 100;
+                sd.update(l..r, x);
+                arr[l..r].iter_mut().for_each(|v| *v = x);
+            }
+            else {
+                let sum = sd.query(l..r, 0,
+                    |acc, v| acc + v,
+                    |acc, block| acc + block.lazy.map_or(*block.summary, |t| t * block.values.len() as u64),
+                );
+                assert_eq!(sum, arr[l..r].iter().sum::<u64>());
+            }
+        }
+        assert_eq!(sd.query(.., 0, |acc, _| acc + 1, |acc, block| acc + block.values.len()), 90);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+
+/// Queue with an O(1) amortized fold over all its elements for any associative function, built from two stacks
+pub struct SwagQueue<T: Clone, F: Fn(T, T) -> T> {
+    // front[i] = (value, fold of the values from front[i] to front[0]), the top is the front of the queue
+    front: Vec<(T, T)>,
+    back: Vec<T>,
+    back_fold: Option<T>,
+    f: F,
+}
+
+impl<T: Clone, F: Fn(T, T) -> T> SwagQueue<T, F> {
+    pub fn new(f: F) -> Self {
+        Self {
+            front: vec![],
+            back: vec![],
+            back_fold: None,
+            f,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.front.last().map(|(value, _)| value).or(self.back.first())
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.back_fold = Some(match self.back_fold.take() {
+            Some(fold) => (self.f)(fold, value.clone()),
+            None => value.clone(),
+        });
+        self.back.push(value);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.front.is_empty() {
+            self.back_fold = None;
+            while let Some(value) = self.back.pop() {
+                let fold = match self.front.last() {
+                    Some((_, fold)) => (self.f)(value.clone(), fold.clone()),
+                    None => value.clone(),
+                };
+                self.front.push((value, fold));
+            }
+        }
+        self.front.pop().map(|(value, _)| value)
+    }
+
+    /// Folds all elements from front to back, `None` if empty
+    pub fn fold(&self) -> Option<T> {
+        match (self.front.last(), &self.back_fold) {
+            (Some((_, a)), Some(b)) => Some((self.f)(a.clone(), b.clone())),
+            (Some((_, a)), None) => Some(a.clone()),
+            (None, b) => b.clone(),
+        }
+    }
+}
+
+/// Minimum (or maximum) of a sliding window in O(1) amortized per operation
+pub struct MonotonicDeque<T: Ord> {
+    // (index, value) with values strictly increasing (decreasing for max) from front to back
+    deque: VecDeque<(usize, T)>,
+    front_index: usize,
+    back_index: usize,
+    max: bool,
+}
+
+impl<T: Ord> MonotonicDeque<T> {
+    pub fn new_min() -> Self {
+        Self::new(false)
+    }
+
+    pub fn new_max() -> Self {
+        Self::new(true)
+    }
+
+    fn new(max: bool) -> Self {
+        Self {
+            deque: VecDeque::new(),
+            front_index: 0,
+            back_index: 0,
+            max,
+        }
+    }
+
+    /// Number of elements in the window
+    pub fn len(&self) -> usize {
+        self.back_index - self.front_index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        while let Some((_, back)) = self.deque.back() {
+            let dominated = if self.max { *back <= value } else { *back >= value };
+            if !dominated {
+                break;
+            }
+            self.deque.pop_back();
+        }
+        self.deque.push_back((self.back_index, value));
+        self.back_index += 1;
+    }
+
+    /// Removes the oldest element of the window, panics if empty
+    pub fn pop_front(&mut self) {
+        assert!(!self.is_empty(), "pop_front on an empty window");
+        if self.deque.front().is_some_and(|(i, _)| *i == self.front_index) {
+            self.deque.pop_front();
+        }
+        self.front_index += 1;
+    }
+
+    /// Minimum (or maximum) of the window, `None` if empty
+    pub fn query(&self) -> Option<&T> {
+        self.deque.front().map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    #[test]
+    fn swag_brute() {
+        let mut rand = random::XorShift::new(17);
+        let mut queue = SwagQueue::new(|a: String, b: String| a + &b);
+        let mut reference = VecDeque::new();
+        for i in 0..3000 {
+            if !rand.next().is_multiple_of(3) {
+                let s = ((b'a' + (i % 26) as u8) as char).to_string();
+                queue.push_back(s.clone());
+                reference.push_back(s);
+            }
+            else {
+                assert_eq!(queue.pop_front(), reference.pop_front());
+            }
+            assert_eq!(queue.len(), reference.len());
+            assert_eq!(queue.front(), reference.front());
+            let expected = reference.iter().cloned().reduce(|a, b| a + &b);
+            assert_eq!(queue.fold(), expected);
+        }
+    }
+
+    #[test]
+    fn sliding_window() {
+        let mut rand = random::XorShift::new(18);
+        let arr = (0..1000).map(|_| rand.next() % 50).collect::<Vec<_>>();
+        for k in [1, 2, 7, 100] {
+            let mut min = MonotonicDeque::new_min();
+            let mut max = MonotonicDeque::new_max();
+            let mut sum = SwagQueue::new(|a, b| a + b);
+            for i in 0..arr.len() {
+                min.push_back(arr[i]);
+                max.push_back(arr[i]);
+                sum.push_back(arr[i]);
+                if i >= k {
+                    min.pop_front();
+                    max.pop_front();
+                    sum.pop_front();
+                }
+                let window = &arr[i.saturating_sub(k-1)..=i];
+                assert_eq!(min.len(), window.len());
+                assert_eq!(min.query(), window.iter().min());
+                assert_eq!(max.query(), window.iter().max());
+                assert_eq!(sum.fold(), Some(window.iter().sum()));
+            }
+        }
+        let mut empty = MonotonicDeque::<i32>::new_min();
+        assert_eq!(empty.query(), None);
+        empty.push_back(3);
+        empty.pop_front();
+        assert!(empty.is_empty());
+    }
+}
@@ -51,6 +51,133 @@ impl<T: Clone + PartialOrd> Treap<T> {
     pub fn len(&self) -> usize {
         self.root.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns the k-th smallest key, 0-indexed
+    pub fn kth(&self, mut k: usize) -> Option<&T> {
+        let mut cur = &self.root;
+        while let TreapNodeCont::Some(node) = cur {
+            let left_len = node.left.len();
+            if k < left_len {
+                cur = &node.left;
+            } else if k == left_len {
+                return Some(&node.key);
+            } else {
+                k -= left_len + 1;
+                cur = &node.right;
+            }
+        }
+        None
+    }
+    /// Returns the number of keys smaller than `key`
+    pub fn rank(&self, key: &T) -> usize {
+        let mut result = 0;
+        let mut cur = &self.root;
+        while let TreapNodeCont::Some(node) = cur {
+            if node.key < *key {
+                result += node.left.len() + 1;
+                cur = &node.right;
+            } else {
+                cur = &node.left;
+            }
+        }
+        result
+    }
+    /// Returns the smallest key for which `pred` is false, `pred` must be true for a prefix of the keys
+    fn first_not(&self, pred: impl Fn(&T) -> bool) -> Option<&T> {
+        let mut result = None;
+        let mut cur = &self.root;
+        while let TreapNodeCont::Some(node) = cur {
+            if pred(&node.key) {
+                cur = &node.right;
+            } else {
+                result = Some(&node.key);
+                cur = &node.left;
+            }
+        }
+        result
+    }
+    /// Returns the largest key for which `pred` is true, `pred` must be true for a prefix of the keys
+    fn last(&self, pred: impl Fn(&T) -> bool) -> Option<&T> {
+        let mut result = None;
+        let mut cur = &self.root;
+        while let TreapNodeCont::Some(node) = cur {
+            if pred(&node.key) {
+                result = Some(&node.key);
+                cur = &node.right;
+            } else {
+                cur = &node.left;
+            }
+        }
+        result
+    }
+    /// Returns the smallest key that is greater than or equal to `key`
+    pub fn lower_bound(&self, key: &T) -> Option<&T> {
+        self.first_not(|k| k < key)
+    }
+    /// Returns the smallest key that is greater than `key`
+    pub fn upper_bound(&self, key: &T) -> Option<&T> {
+        self.first_not(|k| k <= key)
+    }
+    /// Returns the largest key that is smaller than `key`
+    pub fn predecessor(&self, key: &T) -> Option<&T> {
+        self.last(|k| k < key)
+    }
+    /// Returns the smallest key that is greater than `key`, same as `upper_bound`
+    pub fn successor(&self, key: &T) -> Option<&T> {
+        self.upper_bound(key)
+    }
+    pub fn min(&self) -> Option<&T> {
+        self.kth(0)
+    }
+    pub fn max(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|k| self.kth(k))
+    }
+    /// Iterates over the keys in increasing order
+    pub fn iter(&self) -> TreapIter<'_, T> {
+        let mut iter = TreapIter { stack: vec![] };
+        iter.push_left(&self.root);
+        iter
+    }
+}
+
+impl<T: Clone + PartialOrd> Default for Treap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct TreapIter<'a, T: PartialOrd> {
+    stack: Vec<&'a TreapNode<T>>,
+}
+
+impl<'a, T: PartialOrd> TreapIter<'a, T> {
+    fn push_left(&mut self, mut cur: &'a TreapNodeCont<T>) {
+        while let TreapNodeCont::Some(node) = cur {
+            self.stack.push(node);
+            cur = &node.left;
+        }
+    }
+}
+
+impl<'a, T: PartialOrd> Iterator for TreapIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        Some(&node.key)
+    }
+}
+
+impl<'a, T: Clone + PartialOrd> IntoIterator for &'a Treap<T> {
+    type Item = &'a T;
+    type IntoIter = TreapIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 pub struct TreapNode<T: PartialOrd> {
@@ -165,21 +292,13 @@ impl<T: PartialOrd + Clone> TreapNodeCont<T> {
             return TreapNodeCont::Some(left_root);
         };
         if left_root.priority >= right_root.priority {
-            let (left_child, right_child) = (
-                mem::take(&mut left_root.left),
-                mem::take(&mut left_root.right),
-            );
-            left_root.left = left_child.merge(right_child);
-            left_root.right = TreapNodeCont::Some(right_root);
+            let right_child = mem::take(&mut left_root.right);
+            left_root.right = right_child.merge(TreapNodeCont::Some(right_root));
             left_root.recompute();
             TreapNodeCont::Some(left_root)
         } else {
-            let (left_child, right_child) = (
-                mem::take(&mut right_root.left),
-                mem::take(&mut right_root.right),
-            );
-            right_root.right = left_child.merge(right_child);
-            right_root.left = TreapNodeCont::Some(left_root);
+            let left_child = mem::take(&mut right_root.left);
+            right_root.left = TreapNodeCont::Some(left_root).merge(left_child);
             right_root.recompute();
             TreapNodeCont::Some(right_root)
         }
@@ -242,4 +361,119 @@ mod tests {
         assert!(merged.contains(&15));
         assert_eq!(merged.len(), 2);
     }
+
+    #[test]
+    fn test_merge_keeps_order() {
+        let mut rand = crate::random::XorShift::new(10);
+        for _ in 0..20 {
+            let mut left: Treap<u64> = Treap::new();
+            let mut right: Treap<u64> = Treap::new();
+            for _ in 0..100 {
+                left.insert(rand.next() % 100);
+                right.insert(100 + rand.next() % 100);
+            }
+            let mut keys = left.iter().chain(right.iter()).copied().collect::<Vec<_>>();
+            keys.sort();
+
+            let (a, b) = left.merge(right).split(rand.next() % 200);
+            let merged = a.merge(b);
+            assert_eq!(merged.len(), keys.len());
+            assert_eq!(merged.iter().copied().collect::<Vec<_>>(), keys);
+            for (i, key) in keys.iter().enumerate() {
+                assert_eq!(merged.kth(i), Some(key));
+                assert_eq!(merged.rank(key), keys.partition_point(|k| k < key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_order_statistics() {
+        let mut treap: Treap<i32> = Treap::new();
+        for key in [7, 3, 9, 3, 1, 12] {
+            treap.insert(key);
+        }
+
+        assert_eq!(treap.kth(0), Some(&1));
+        assert_eq!(treap.kth(2), Some(&3));
+        assert_eq!(treap.kth(5), Some(&12));
+        assert_eq!(treap.kth(6), None);
+
+        assert_eq!(treap.rank(&1), 0);
+        assert_eq!(treap.rank(&3), 1);
+        assert_eq!(treap.rank(&4), 3);
+        assert_eq!(treap.rank(&100), 6);
+
+        assert_eq!(treap.lower_bound(&3), Some(&3));
+        assert_eq!(treap.lower_bound(&4), Some(&7));
+        assert_eq!(treap.lower_bound(&13), None);
+        assert_eq!(treap.upper_bound(&3), Some(&7));
+        assert_eq!(treap.predecessor(&3), Some(&1));
+        assert_eq!(treap.predecessor(&1), None);
+        assert_eq!(treap.successor(&9), Some(&12));
+        assert_eq!(treap.successor(&12), None);
+
+        assert_eq!(treap.min(), Some(&1));
+        assert_eq!(treap.max(), Some(&12));
+        assert_eq!(Treap::<i32>::new().max(), None);
+
+        assert_eq!(treap.iter().copied().collect::<Vec<_>>(), vec![1, 3, 3, 7, 9, 12]);
+    }
+
+    #[test]
+    fn test_order_statistics_brute() {
+        let mut rand = crate::random::XorShift::new(9);
+        let mut treap: Treap<u64> = Treap::new();
+        let mut keys = vec![];
+        for _ in 0..500 {
+            let key = rand.next() % 200;
+            if rand.next().is_multiple_of(3) {
+                treap.erase(&key);
+                if let Some(pos) = keys.iter().position(|k| *k == key) {
+                    keys.remove(pos);
+                }
+            } else {
+                treap.insert(key);
+                keys.push(key);
+            }
+            keys.sort();
+
+            assert_eq!(treap.iter().copied().collect::<Vec<_>>(), keys);
+            let x = rand.next() % 200;
+            assert_eq!(treap.rank(&x), keys.iter().filter(|k| **k < x).count());
+            assert_eq!(treap.lower_bound(&x), keys.iter().find(|k| **k >= x));
+            assert_eq!(treap.upper_bound(&x), keys.iter().find(|k| **k > x));
+            assert_eq!(treap.predecessor(&x), keys.iter().rev().find(|k| **k < x));
+            if !keys.is_empty() {
+                let k = rand.next() as usize % keys.len();
+                assert_eq!(treap.kth(k), Some(&keys[k]));
+            }
+        }
+    }
+
+    fn shape(cont: &TreapNodeCont<u64>, result: &mut Vec<(u64, u64, usize)>) {
+        if let TreapNodeCont::Some(node) = cont {
+            result.push((node.key, node.priority, node.len));
+            shape(&node.left, result);
+            shape(&node.right, result);
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_structure() {
+        let build = || {
+            let mut treap = Treap::new();
+            for key in 0..1000 {
+                treap.insert(key * 7919 % 1000);
+            }
+            for key in 0..100 {
+                treap.erase(&key);
+            }
+            let mut result = vec![];
+            shape(&treap.root, &mut result);
+            result
+        };
+        let first = crate::random::with_seed(42, build);
+        assert_eq!(first, crate::random::with_seed(42, build));
+        assert_ne!(first, crate::random::with_seed(43, build));
+    }
 }
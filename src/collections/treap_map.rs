@@ -0,0 +1,458 @@
+use std::{mem, ops::{Bound, RangeBounds}};
+
+use super::super::random::rand_u64;
+
+type Link<K, V> = Option<Box<TreapMapNode<K, V>>>;
+
+struct TreapMapNode<K, V> {
+    key: K,
+    val: V,
+    // aggregate of the values in the subtree, `None` for maps with `NoAggregate`
+    agg: Option<V>,
+    // set on the path to a value changed through `get_mut`, whose aggregates have to be recomputed
+    dirty: bool,
+    left: Link<K, V>,
+    right: Link<K, V>,
+    priority: u64,
+    len: usize,
+}
+
+fn link_len<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.len)
+}
+
+fn after_start<K: Ord>(key: &K, start: Bound<&K>) -> bool {
+    match start {
+        Bound::Included(s) => key >= s,
+        Bound::Excluded(s) => key > s,
+        Bound::Unbounded => true,
+    }
+}
+
+fn before_end<K: Ord>(key: &K, end: Bound<&K>) -> bool {
+    match end {
+        Bound::Included(e) => key <= e,
+        Bound::Excluded(e) => key < e,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Aggregate function of a `TreapMap`, either `NoAggregate` or an associative `Fn(V, V) -> V`
+pub trait MapAggregate<V> {
+    /// False if the map doesn't maintain aggregates, `combine` is never called then
+    const ENABLED: bool = true;
+
+    fn combine(&self, a: V, b: V) -> V;
+}
+
+impl<V, F: Fn(V, V) -> V> MapAggregate<V> for F {
+    fn combine(&self, a: V, b: V) -> V {
+        self(a, b)
+    }
+}
+
+/// Aggregate of a `TreapMap` created by `new`, which has no `query`
+pub struct NoAggregate;
+
+impl<V> MapAggregate<V> for NoAggregate {
+    const ENABLED: bool = false;
+
+    fn combine(&self, _: V, _: V) -> V {
+        unreachable!("NoAggregate doesn't combine values")
+    }
+}
+
+/// Ordered map with rank queries and, when created by `with_aggregate`, aggregates of the values over key ranges
+pub struct TreapMap<K: Ord, V, A: MapAggregate<V> = NoAggregate> {
+    root: Link<K, V>,
+    f: A,
+}
+
+impl<K: Ord, V: Clone> Default for TreapMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V: Clone> TreapMap<K, V> {
+    pub fn new() -> Self {
+        Self { root: None, f: NoAggregate }
+    }
+}
+
+impl<K: Ord, V: Clone, F: Fn(V, V) -> V> TreapMap<K, V, F> {
+    /// `f` has to be associative, `query` then returns `f` over the values in a key range
+    pub fn with_aggregate(f: F) -> Self {
+        Self { root: None, f }
+    }
+}
+
+impl<K: Ord, V: Clone, A: MapAggregate<V>> TreapMap<K, V, A> {
+    pub fn len(&self) -> usize {
+        link_len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn merge_vals(&self, a: Option<V>, b: Option<V>) -> Option<V> {
+        let Some(a) = a else {
+            return b;
+        };
+        let Some(b) = b else {
+            return Some(a);
+        };
+        Some(self.f.combine(a, b))
+    }
+
+    fn recompute(&self, node: &mut TreapMapNode<K, V>) {
+        node.len = link_len(&node.left) + link_len(&node.right) + 1;
+        if A::ENABLED {
+            let left = node.left.as_ref().and_then(|left| left.agg.clone());
+            let right = node.right.as_ref().and_then(|right| right.agg.clone());
+            let agg = self.merge_vals(left, Some(node.val.clone()));
+            node.agg = self.merge_vals(agg, right);
+        }
+    }
+
+    fn refresh(&self, link: &mut Link<K, V>) {
+        let Some(node) = link else {
+            return;
+        };
+        if !node.dirty {
+            return;
+        }
+        self.refresh(&mut node.left);
+        self.refresh(&mut node.right);
+        self.recompute(node);
+        node.dirty = false;
+    }
+
+    /// Splits into keys `< key` and `>= key`
+    fn split_node(&self, link: Link<K, V>, key: &K) -> (Link<K, V>, Link<K, V>) {
+        let Some(mut root) = link else {
+            return (None, None);
+        };
+        if root.key < *key {
+            let (left, right) = self.split_node(root.right.take(), key);
+            root.right = left;
+            self.recompute(&mut root);
+            (Some(root), right)
+        } else {
+            let (left, right) = self.split_node(root.left.take(), key);
+            root.left = right;
+            self.recompute(&mut root);
+            (left, Some(root))
+        }
+    }
+
+    fn merge_node(&self, left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+        let Some(mut left_root) = left else {
+            return right;
+        };
+        let Some(mut right_root) = right else {
+            return Some(left_root);
+        };
+        if left_root.priority >= right_root.priority {
+            left_root.right = self.merge_node(left_root.right.take(), Some(right_root));
+            self.recompute(&mut left_root);
+            Some(left_root)
+        } else {
+            right_root.left = self.merge_node(Some(left_root), right_root.left.take());
+            self.recompute(&mut right_root);
+            Some(right_root)
+        }
+    }
+
+    fn insert_node(&self, link: Link<K, V>, mut node: Box<TreapMapNode<K, V>>) -> Link<K, V> {
+        let Some(mut root) = link else {
+            return Some(node);
+        };
+        if node.priority >= root.priority {
+            (node.left, node.right) = self.split_node(Some(root), &node.key);
+            self.recompute(&mut node);
+            Some(node)
+        } else {
+            if node.key < root.key {
+                root.left = self.insert_node(root.left.take(), node);
+            } else {
+                root.right = self.insert_node(root.right.take(), node);
+            }
+            self.recompute(&mut root);
+            Some(root)
+        }
+    }
+
+    fn remove_node(&self, link: &mut Link<K, V>, key: &K) -> Option<V> {
+        let node = link.as_mut()?;
+        let result = match key.cmp(&node.key) {
+            std::cmp::Ordering::Less => self.remove_node(&mut node.left, key),
+            std::cmp::Ordering::Greater => self.remove_node(&mut node.right, key),
+            std::cmp::Ordering::Equal => {
+                let mut node = link.take().unwrap();
+                *link = self.merge_node(node.left.take(), node.right.take());
+                return Some(node.val);
+            }
+        };
+        if result.is_some() {
+            self.recompute(node);
+        }
+        result
+    }
+
+    fn find(&self, key: &K) -> Option<&TreapMapNode<K, V>> {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            match key.cmp(&node.key) {
+                std::cmp::Ordering::Less => cur = &node.left,
+                std::cmp::Ordering::Greater => cur = &node.right,
+                std::cmp::Ordering::Equal => return Some(node),
+            }
+        }
+        None
+    }
+
+    /// Inserts the value, returning the previous value of the key if there was one
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        if let Some(old) = self.get_mut(&key) {
+            return Some(mem::replace(old, val));
+        }
+        let mut root = self.root.take();
+        self.refresh(&mut root);
+        let node = Box::new(TreapMapNode {
+            key,
+            agg: A::ENABLED.then(|| val.clone()),
+            val,
+            dirty: false,
+            left: None,
+            right: None,
+            priority: rand_u64(),
+            len: 1,
+        });
+        self.root = self.insert_node(root, node);
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let mut root = self.root.take();
+        self.refresh(&mut root);
+        let result = self.remove_node(&mut root, key);
+        self.root = root;
+        result
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|node| &node.val)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let mut cur = &mut self.root;
+        while let Some(node) = cur {
+            node.dirty = true;
+            match key.cmp(&node.key) {
+                std::cmp::Ordering::Less => cur = &mut node.left,
+                std::cmp::Ordering::Greater => cur = &mut node.right,
+                std::cmp::Ordering::Equal => return Some(&mut node.val),
+            }
+        }
+        None
+    }
+
+    /// Returns the number of keys smaller than `key`
+    pub fn rank(&self, key: &K) -> usize {
+        let mut result = 0;
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            if node.key < *key {
+                result += link_len(&node.left) + 1;
+                cur = &node.right;
+            } else {
+                cur = &node.left;
+            }
+        }
+        result
+    }
+
+    /// Returns the entry with the k-th smallest key, 0-indexed
+    pub fn kth(&self, mut k: usize) -> Option<(&K, &V)> {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            let left_len = link_len(&node.left);
+            if k < left_len {
+                cur = &node.left;
+            } else if k == left_len {
+                return Some((&node.key, &node.val));
+            } else {
+                k -= left_len + 1;
+                cur = &node.right;
+            }
+        }
+        None
+    }
+
+    /// Iterates over the entries with keys in `range` in increasing order of keys
+    pub fn range<'a>(&'a self, range: impl RangeBounds<K> + 'a) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
+        let mut stack = vec![];
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            if after_start(&node.key, range.start_bound()) {
+                stack.push(node.as_ref());
+                cur = &node.left;
+            } else {
+                cur = &node.right;
+            }
+        }
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            if !before_end(&node.key, range.end_bound()) {
+                stack.clear();
+                return None;
+            }
+            let mut cur = &node.right;
+            while let Some(child) = cur {
+                stack.push(child.as_ref());
+                cur = &child.left;
+            }
+            Some((&node.key, &node.val))
+        })
+    }
+
+    /// Iterates over all the entries in increasing order of keys
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.range(..)
+    }
+}
+
+impl<K: Ord, V: Clone, F: Fn(V, V) -> V> TreapMap<K, V, F> {
+    /// Aggregate of the values with keys `>= start` in the subtree
+    fn suffix_agg(&self, link: &Link<K, V>, start: Bound<&K>) -> Option<V> {
+        let node = link.as_ref()?;
+        if after_start(&node.key, start) {
+            let right = node.right.as_ref().and_then(|right| right.agg.clone());
+            let mid = self.merge_vals(Some(node.val.clone()), right);
+            self.merge_vals(self.suffix_agg(&node.left, start), mid)
+        } else {
+            self.suffix_agg(&node.right, start)
+        }
+    }
+
+    /// Aggregate of the values with keys `<= end` in the subtree
+    fn prefix_agg(&self, link: &Link<K, V>, end: Bound<&K>) -> Option<V> {
+        let node = link.as_ref()?;
+        if before_end(&node.key, end) {
+            let left = node.left.as_ref().and_then(|left| left.agg.clone());
+            let mid = self.merge_vals(left, Some(node.val.clone()));
+            self.merge_vals(mid, self.prefix_agg(&node.right, end))
+        } else {
+            self.prefix_agg(&node.left, end)
+        }
+    }
+
+    /// Aggregate of the values with keys in `range`, `None` if there are none
+    pub fn query(&mut self, range: impl RangeBounds<K>) -> Option<V> {
+        let mut root = self.root.take();
+        self.refresh(&mut root);
+        self.root = root;
+
+        let (start, end) = (range.start_bound(), range.end_bound());
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            if !after_start(&node.key, start) {
+                cur = &node.right;
+            } else if !before_end(&node.key, end) {
+                cur = &node.left;
+            } else {
+                let left = self.suffix_agg(&node.left, start);
+                let mid = self.merge_vals(left, Some(node.val.clone()));
+                return self.merge_vals(mid, self.prefix_agg(&node.right, end));
+            }
+        }
+        None
+    }
+}
+
+impl<K: Ord + Clone, V: Clone, A: MapAggregate<V>> TreapMap<K, V, A> {
+    /// Returns the value of the key, inserting `default()` first if it isn't present
+    pub fn get_or_insert_with(&mut self, key: K, default: impl FnOnce() -> V) -> &mut V {
+        if !self.contains_key(&key) {
+            self.insert(key.clone(), default());
+        }
+        self.get_mut(&key).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::random::XorShift;
+
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let mut map = TreapMap::new();
+        assert_eq!(map.insert(5, "five"), None);
+        assert_eq!(map.insert(1, "one"), None);
+        assert_eq!(map.insert(5, "FIVE"), Some("five"));
+        *map.get_or_insert_with(3, || "three") = "THREE";
+        assert_eq!(map.get(&3), Some(&"THREE"));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.rank(&4), 2);
+        assert_eq!(map.kth(2), Some((&5, &"FIVE")));
+        assert_eq!(map.range(2..).map(|(k, _)| *k).collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(map.remove(&1), Some("one"));
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.iter().map(|(_, v)| *v).collect::<Vec<_>>(), vec!["THREE", "FIVE"]);
+    }
+
+    #[test]
+    fn test_brute() {
+        let mut rand = XorShift::new(12);
+        let mut map = TreapMap::with_aggregate(|a: i64, b: i64| a+b);
+        let mut tar = BTreeMap::new();
+        for _ in 0..3000 {
+            let key = rand.next() % 200;
+            let val = (rand.next() % 1000) as i64 - 500;
+            match rand.next() % 5 {
+                0 => assert_eq!(map.insert(key, val), tar.insert(key, val)),
+                1 => assert_eq!(map.remove(&key), tar.remove(&key)),
+                2 => {
+                    if let Some(v) = map.get_mut(&key) {
+                        *v += val;
+                    }
+                    if let Some(v) = tar.get_mut(&key) {
+                        *v += val;
+                    }
+                }
+                3 => {
+                    *map.get_or_insert_with(key, || 0) -= val;
+                    *tar.entry(key).or_insert(0) -= val;
+                }
+                _ => {
+                    let mut l = rand.next() % 200;
+                    let mut r = rand.next() % 200;
+                    if l > r {
+                        std::mem::swap(&mut l, &mut r);
+                    }
+                    let expected = tar.range(l..r).map(|(_, v)| *v).reduce(|a, b| a+b);
+                    assert_eq!(map.query(l..r), expected);
+                    let expected = tar.range(l..=r).map(|(_, v)| *v).reduce(|a, b| a+b);
+                    assert_eq!(map.query(l..=r), expected);
+                    assert!(map.range(l..r).map(|(k, v)| (*k, *v)).eq(tar.range(l..r).map(|(k, v)| (*k, *v))));
+                }
+            }
+            assert_eq!(map.get(&key), tar.get(&key));
+            assert_eq!(map.len(), tar.len());
+            assert_eq!(map.rank(&key), tar.range(..key).count());
+        }
+    }
+}
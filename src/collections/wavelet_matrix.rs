@@ -0,0 +1,394 @@
+use std::{cmp::Reverse, collections::BinaryHeap, ops::{Bound, Range, RangeBounds}};
+
+use super::to_range;
+
+/// Static bit vector with O(1) rank and O(log n) select
+pub struct BitVector {
+    words: Vec<u64>,
+    // ranks[w] = number of ones in words[..w]
+    ranks: Vec<usize>,
+    len: usize,
+}
+
+impl BitVector {
+    pub fn new_from_iter(iter: impl IntoIterator<Item=bool>) -> Self {
+        let mut words: Vec<u64> = vec![];
+        let mut len = 0;
+        for bit in iter {
+            if len % 64 == 0 {
+                words.push(0);
+            }
+            if bit {
+                *words.last_mut().unwrap() |= 1 << (len % 64);
+            }
+            len += 1;
+        }
+        let mut ranks = vec![0; words.len()+1];
+        for (w, word) in words.iter().enumerate() {
+            ranks[w+1] = ranks[w] + word.count_ones() as usize;
+        }
+        Self { words, ranks, len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len);
+        self.words[i/64] >> (i%64) & 1 == 1
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.ranks[self.words.len()]
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Number of ones in `[0, i)`
+    pub fn rank1(&self, i: usize) -> usize {
+        assert!(i <= self.len);
+        let (w, b) = (i/64, i%64);
+        if b == 0 {
+            self.ranks[w]
+        }
+        else {
+            self.ranks[w] + (self.words[w] & ((1 << b) - 1)).count_ones() as usize
+        }
+    }
+
+    /// Number of zeros in `[0, i)`
+    pub fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+
+    /// Position of the `k`-th (0-indexed) one
+    pub fn select1(&self, k: usize) -> Option<usize> {
+        if k >= self.count_ones() {
+            return None;
+        }
+        let w = self.ranks.partition_point(|&r| r <= k) - 1;
+        Some(w*64 + select_in_word(self.words[w], k - self.ranks[w]))
+    }
+
+    /// Position of the `k`-th (0-indexed) zero
+    pub fn select0(&self, k: usize) -> Option<usize> {
+        if k >= self.count_zeros() {
+            return None;
+        }
+        let zeros_before = |w: usize| w*64 - self.ranks[w];
+        // largest w with zeros_before(w) <= k
+        let (mut lo, mut hi) = (0, self.words.len());
+        while hi - lo > 1 {
+            let mid = (lo+hi)/2;
+            if zeros_before(mid) <= k {
+                lo = mid;
+            }
+            else {
+                hi = mid;
+            }
+        }
+        Some(lo*64 + select_in_word(!self.words[lo], k - zeros_before(lo)))
+    }
+}
+
+/// Position of the `k`-th (0-indexed) set bit of `word`
+fn select_in_word(mut word: u64, k: usize) -> usize {
+    for _ in 0..k {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+/// Static sequence of `u64` answering range k-th smallest, range frequency and similar queries in O(log max)
+pub struct WaveletMatrix {
+    // levels[0] holds the highest bit
+    levels: Vec<BitVector>,
+    len: usize,
+}
+
+impl WaveletMatrix {
+    pub fn new_from_iter(iter: impl IntoIterator<Item=u64>) -> Self {
+        let mut values = iter.into_iter().collect::<Vec<_>>();
+        let len = values.len();
+        let bits = values.iter().max().map_or(0, |mx| 64 - mx.leading_zeros() as usize);
+        let mut levels = Vec::with_capacity(bits);
+        for bit in (0..bits).rev() {
+            levels.push(BitVector::new_from_iter(values.iter().map(|v| v >> bit & 1 == 1)));
+            let (mut zeros, ones): (Vec<_>, Vec<_>) = values.into_iter().partition(|v| v >> bit & 1 == 0);
+            zeros.extend(ones);
+            values = zeros;
+        }
+        Self { levels, len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Maps `[l, r)` on `level` to the next level following `bit`
+    fn descend(&self, level: usize, l: usize, r: usize, bit: bool) -> (usize, usize) {
+        let lv = &self.levels[level];
+        if bit {
+            (lv.count_zeros() + lv.rank1(l), lv.count_zeros() + lv.rank1(r))
+        }
+        else {
+            (lv.rank0(l), lv.rank0(r))
+        }
+    }
+
+    fn bit(&self, x: u64, level: usize) -> bool {
+        x >> (self.levels.len() - 1 - level) & 1 == 1
+    }
+
+    /// Returns true if `x` does not fit in the number of bits of the largest element
+    fn too_large(&self, x: u64) -> bool {
+        x.checked_shr(self.levels.len() as u32).unwrap_or(0) != 0
+    }
+
+    pub fn get(&self, i: usize) -> u64 {
+        assert!(i < self.len);
+        let mut i = i;
+        let mut result = 0;
+        for level in 0..self.levels.len() {
+            let bit = self.levels[level].get(i);
+            result = result << 1 | bit as u64;
+            i = self.descend(level, i, i, bit).0;
+        }
+        result
+    }
+
+    /// Number of occurrences of `x` in the range
+    pub fn count(&self, range: impl RangeBounds<usize>, x: u64) -> usize {
+        let Range { start: mut l, end: mut r } = to_range(range, self.len);
+        if l >= r || self.too_large(x) {
+            return 0;
+        }
+        for level in 0..self.levels.len() {
+            (l, r) = self.descend(level, l, r, self.bit(x, level));
+        }
+        r - l
+    }
+
+    /// Position of the `k`-th (0-indexed) occurrence of `x`
+    pub fn select(&self, x: u64, k: usize) -> Option<usize> {
+        if self.too_large(x) {
+            return None;
+        }
+        let (mut l, mut r) = (0, self.len);
+        for level in 0..self.levels.len() {
+            (l, r) = self.descend(level, l, r, self.bit(x, level));
+        }
+        if k >= r - l {
+            return None;
+        }
+        let mut pos = l + k;
+        for level in (0..self.levels.len()).rev() {
+            let lv = &self.levels[level];
+            pos = if self.bit(x, level) {
+                lv.select1(pos - lv.count_zeros()).unwrap()
+            }
+            else {
+                lv.select0(pos).unwrap()
+            };
+        }
+        Some(pos)
+    }
+
+    /// Returns the `k`-th (0-indexed) smallest value in the range
+    pub fn kth_smallest(&self, range: impl RangeBounds<usize>, k: usize) -> Option<u64> {
+        let Range { start: mut l, end: mut r } = to_range(range, self.len);
+        if l >= r || k >= r - l {
+            return None;
+        }
+        let mut k = k;
+        let mut result = 0;
+        for level in 0..self.levels.len() {
+            let zeros = self.levels[level].rank0(r) - self.levels[level].rank0(l);
+            let bit = k >= zeros;
+            if bit {
+                k -= zeros;
+            }
+            result = result << 1 | bit as u64;
+            (l, r) = self.descend(level, l, r, bit);
+        }
+        Some(result)
+    }
+
+    /// Returns the `k`-th (0-indexed) largest value in the range
+    pub fn kth_largest(&self, range: impl RangeBounds<usize>, k: usize) -> Option<u64> {
+        let Range { start: l, end: r } = to_range(range, self.len);
+        if l >= r || k >= r - l {
+            return None;
+        }
+        self.kth_smallest(l..r, r - l - 1 - k)
+    }
+
+    /// Number of values less than `x` in the range
+    pub fn count_less(&self, range: impl RangeBounds<usize>, x: u64) -> usize {
+        let Range { start: mut l, end: mut r } = to_range(range, self.len);
+        if l >= r {
+            return 0;
+        }
+        if self.too_large(x) {
+            return r - l;
+        }
+        let mut result = 0;
+        for level in 0..self.levels.len() {
+            let bit = self.bit(x, level);
+            if bit {
+                result += self.levels[level].rank0(r) - self.levels[level].rank0(l);
+            }
+            (l, r) = self.descend(level, l, r, bit);
+        }
+        result
+    }
+
+    /// Number of values within `values` in the range
+    pub fn count_range(&self, range: impl RangeBounds<usize>, values: impl RangeBounds<u64>) -> usize {
+        let Range { start: l, end: r } = to_range(range, self.len);
+        let below_end = match values.end_bound() {
+            Bound::Included(&u64::MAX) | Bound::Unbounded => r.saturating_sub(l),
+            Bound::Included(&p) => self.count_less(l..r, p + 1),
+            Bound::Excluded(&p) => self.count_less(l..r, p),
+        };
+        let below_start = match values.start_bound() {
+            Bound::Included(&p) => self.count_less(l..r, p),
+            Bound::Excluded(&u64::MAX) => r.saturating_sub(l),
+            Bound::Excluded(&p) => self.count_less(l..r, p + 1),
+            Bound::Unbounded => 0,
+        };
+        below_end.saturating_sub(below_start)
+    }
+
+    /// Returns up to `k` most frequent values in the range with their counts,
+    /// by decreasing count and then increasing value
+    pub fn top_k(&self, range: impl RangeBounds<usize>, k: usize) -> Vec<(u64, usize)> {
+        let Range { start: l, end: r } = to_range(range, self.len);
+        let mut result = vec![];
+        if l >= r {
+            return result;
+        }
+        let bits = self.levels.len();
+        // (count, smallest value in the subtree, level, l, r)
+        let mut heap = BinaryHeap::new();
+        heap.push((r - l, Reverse(0u64), 0, l, r));
+        while let Some((cnt, Reverse(value), level, l, r)) = heap.pop() {
+            if result.len() == k {
+                break;
+            }
+            if level == bits {
+                result.push((value, cnt));
+                continue;
+            }
+            for bit in [false, true] {
+                let (nl, nr) = self.descend(level, l, r, bit);
+                if nl < nr {
+                    let value = value | (bit as u64) << (bits - 1 - level);
+                    heap.push((nr - nl, Reverse(value), level+1, nl, nr));
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    #[test]
+    fn bit_vector_rank_select() {
+        let mut rand = random::XorShift::new(1);
+        let bits = (0..500).map(|_| rand.next().is_multiple_of(3)).collect::<Vec<_>>();
+        let bv = BitVector::new_from_iter(bits.clone());
+        assert_eq!(bv.len(), 500);
+        let ones = (0..500).filter(|&i| bits[i]).collect::<Vec<_>>();
+        let zeros = (0..500).filter(|&i| !bits[i]).collect::<Vec<_>>();
+        for i in 0..=500 {
+            assert_eq!(bv.rank1(i), bits[..i].iter().filter(|b| **b).count());
+            assert_eq!(bv.rank0(i), bits[..i].iter().filter(|b| !**b).count());
+        }
+        for (k, &i) in ones.iter().enumerate() {
+            assert_eq!(bv.select1(k), Some(i));
+        }
+        for (k, &i) in zeros.iter().enumerate() {
+            assert_eq!(bv.select0(k), Some(i));
+        }
+        assert_eq!(bv.select1(ones.len()), None);
+        assert_eq!(bv.select0(zeros.len()), None);
+    }
+
+    #[test]
+    fn queries_brute() {
+        let mut rand = random::XorShift::new(2);
+        for max in [1, 2, 10, 1000, u64::MAX] {
+            let arr = (0..150).map(|_| if max == u64::MAX { rand.next() % 8 * (u64::MAX / 7) } else { rand.next() % max }).collect::<Vec<_>>();
+            let wm = WaveletMatrix::new_from_iter(arr.clone());
+            for (i, &v) in arr.iter().enumerate() {
+                assert_eq!(wm.get(i), v);
+            }
+            for _ in 0..300 {
+                let l = rand.next() as usize % 151;
+                let r = l + rand.next() as usize % (151 - l);
+                let x = arr[rand.next() as usize % arr.len()];
+                let mut sorted = arr[l..r].to_vec();
+                sorted.sort();
+
+                for k in 0..=sorted.len() {
+                    assert_eq!(wm.kth_smallest(l..r, k), sorted.get(k).copied());
+                    assert_eq!(wm.kth_largest(l..r, k), sorted.iter().rev().nth(k).copied());
+                }
+                assert_eq!(wm.count(l..r, x), sorted.iter().filter(|v| **v == x).count());
+                assert_eq!(wm.count_less(l..r, x), sorted.iter().filter(|v| **v < x).count());
+                assert_eq!(wm.count_less(l..r, x.wrapping_add(1)), sorted.iter().filter(|v| **v < x.wrapping_add(1)).count());
+                let y = arr[rand.next() as usize % arr.len()];
+                assert_eq!(wm.count_range(l..r, x..=y), sorted.iter().filter(|v| (x..=y).contains(*v)).count());
+                assert_eq!(wm.count_range(l..r, x..y), sorted.iter().filter(|v| (x..y).contains(*v)).count());
+                assert_eq!(wm.count_range(l..r, ..), sorted.len());
+
+                let mut freq = std::collections::BTreeMap::new();
+                for v in &sorted {
+                    *freq.entry(*v).or_insert(0) += 1;
+                }
+                let mut expected = freq.into_iter().collect::<Vec<_>>();
+                expected.sort_by_key(|&(v, c)| (Reverse(c), v));
+                expected.truncate(3);
+                assert_eq!(wm.top_k(l..r, 3), expected);
+            }
+
+            let x = arr[0];
+            let positions = (0..arr.len()).filter(|&i| arr[i] == x).collect::<Vec<_>>();
+            for (k, &i) in positions.iter().enumerate() {
+                assert_eq!(wm.select(x, k), Some(i));
+            }
+            assert_eq!(wm.select(x, positions.len()), None);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let wm = WaveletMatrix::new_from_iter(vec![]);
+        assert!(wm.is_empty());
+        assert_eq!(wm.kth_smallest(.., 0), None);
+        assert_eq!(wm.count_less(.., 5), 0);
+        assert!(wm.top_k(.., 2).is_empty());
+
+        let wm = WaveletMatrix::new_from_iter(vec![0, 0, 0]);
+        assert_eq!(wm.kth_smallest(.., 2), Some(0));
+        assert_eq!(wm.count(.., 0), 3);
+        assert_eq!(wm.count_less(.., 1), 3);
+        assert_eq!(wm.select(0, 1), Some(1));
+    }
+}
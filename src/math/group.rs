@@ -0,0 +1,115 @@
+use std::{num::Wrapping, ops::{Add, Sub}};
+
+use super::{fractions::Fract, modular::{MInt, MInt128}};
+
+pub trait AdditiveGroup: Sized + Copy + Add<Self, Output = Self> + Sub<Self, Output = Self> {
+    fn zero() -> Self;
+
+    /// Adds `self` to itself `k` times
+    fn mul_usize(self, mut k: usize) -> Self {
+        let mut result = Self::zero();
+        let mut base = self;
+        while k > 0 {
+            if k % 2 == 1 {
+                result = result + base;
+            }
+            base = base + base;
+            k /= 2;
+        }
+        result
+    }
+}
+
+macro_rules! impl_additive_group {
+    ($t: ty) => {
+        impl AdditiveGroup for $t {
+            fn zero() -> Self {
+                0 as $t
+            }
+            fn mul_usize(self, k: usize) -> Self {
+                self * k as $t
+            }
+        }
+    };
+}
+
+impl_additive_group!(isize);
+impl_additive_group!(i32);
+impl_additive_group!(i64);
+impl_additive_group!(i128);
+impl_additive_group!(f32);
+impl_additive_group!(f64);
+
+// unsigned integers have no negation, wrapping arithmetic makes them a group mod 2^k
+macro_rules! impl_wrapping_group {
+    ($t: ty) => {
+        impl AdditiveGroup for Wrapping<$t> {
+            fn zero() -> Self {
+                Wrapping(0)
+            }
+            fn mul_usize(self, k: usize) -> Self {
+                self * Wrapping(k as $t)
+            }
+        }
+    };
+}
+
+impl_wrapping_group!(usize);
+impl_wrapping_group!(u32);
+impl_wrapping_group!(u64);
+impl_wrapping_group!(u128);
+
+impl<const M: u64> AdditiveGroup for MInt<M> {
+    fn zero() -> Self {
+        MInt::zero()
+    }
+    fn mul_usize(self, k: usize) -> Self {
+        self * MInt::new(k as u64)
+    }
+}
+
+impl<const M: u128> AdditiveGroup for MInt128<M> {
+    fn zero() -> Self {
+        MInt128::zero()
+    }
+    fn mul_usize(self, k: usize) -> Self {
+        self * MInt128::new(k as u128)
+    }
+}
+
+impl AdditiveGroup for Fract {
+    fn zero() -> Self {
+        Fract::from(0)
+    }
+}
+
+/// Xor as the group operation, `Xor(1)` on every edge of a `WeightedDisjointSet` tracks parity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Xor(pub u64);
+
+impl Add for Xor {
+    type Output = Xor;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Xor) -> Self::Output {
+        Xor(self.0 ^ rhs.0)
+    }
+}
+
+impl Sub for Xor {
+    type Output = Xor;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Xor) -> Self::Output {
+        Xor(self.0 ^ rhs.0)
+    }
+}
+
+impl AdditiveGroup for Xor {
+    fn zero() -> Self {
+        Xor(0)
+    }
+    fn mul_usize(self, k: usize) -> Self {
+        if k % 2 == 1 { self } else { Xor(0) }
+    }
+}
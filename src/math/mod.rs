@@ -3,6 +3,9 @@ pub mod primes;
 pub mod integer;
 pub use integer::Integer;
 
+pub mod group;
+pub use group::AdditiveGroup;
+
 pub mod modular;
 
 pub mod fractions;
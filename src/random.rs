@@ -1,14 +1,39 @@
-use std::time::SystemTime;
+use std::{cell::RefCell, time::SystemTime};
 
-pub static mut RNG: Option<XorShift> = None;
+thread_local! {
+    static RNG: RefCell<Option<XorShift>> = const { RefCell::new(None) };
+}
 
+/// Returns the next number of the thread's global generator, which is seeded from the current time unless `seed` was called
 pub fn rand_u64() -> u64 {
-    let rng = unsafe {
-        RNG.get_or_insert(XorShift::new(
-            (SystemTime::UNIX_EPOCH.elapsed().unwrap().as_nanos() & 0xFFFFFFFFFFFFFFFF) as u64,
-        ))
-    };
-    rng.next()
+    RNG.with(|rng| {
+        rng.borrow_mut()
+            .get_or_insert_with(|| {
+                XorShift::new((SystemTime::UNIX_EPOCH.elapsed().unwrap().as_nanos() & 0xFFFFFFFFFFFFFFFF) as u64)
+            })
+            .next()
+    })
+}
+
+/// Reseeds the thread's global generator, making everything built on `rand_u64` (e.g. treap priorities) reproducible
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = Some(XorShift::new(seed)));
+}
+
+/// Runs `f` with the thread's global generator seeded by `seed` and restores the previous generator afterwards
+pub fn with_seed<R>(seed: u64, f: impl FnOnce() -> R) -> R {
+    // restores the previous generator on drop, so it also happens if `f` panics
+    struct Restore(Option<XorShift>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let prev = self.0.take();
+            RNG.with(|rng| *rng.borrow_mut() = prev);
+        }
+    }
+
+    let _restore = Restore(RNG.with(|rng| rng.borrow_mut().replace(XorShift::new(seed))));
+    f()
 }
 
 pub struct XorShift {
@@ -37,3 +62,41 @@ impl XorShift {
         self.w
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed() {
+        seed(7);
+        let a = (0..10).map(|_| rand_u64()).collect::<Vec<_>>();
+        seed(7);
+        let b = (0..10).map(|_| rand_u64()).collect::<Vec<_>>();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_with_seed_restores() {
+        seed(1);
+        let first = rand_u64();
+        let scoped = with_seed(2, || (rand_u64(), rand_u64()));
+        assert_eq!(scoped, with_seed(2, || (rand_u64(), rand_u64())));
+        let second = rand_u64();
+
+        seed(1);
+        assert_eq!((first, second), (rand_u64(), rand_u64()));
+    }
+
+    #[test]
+    fn test_with_seed_restores_on_panic() {
+        seed(1);
+        let first = rand_u64();
+        let result = std::panic::catch_unwind(|| with_seed(2, || panic!("inside with_seed")));
+        assert!(result.is_err());
+        let second = rand_u64();
+
+        seed(1);
+        assert_eq!((first, second), (rand_u64(), rand_u64()));
+    }
+}